@@ -1,14 +1,59 @@
-use crate::windows::TilingMode;
+use crate::{
+    layout::Layout,
+    monitor::Direction,
+    tree::{ContainerMode, TilingDirection},
+    windows::TilingMode,
+};
 
 pub enum WmAction {
     Workspace(WorkspaceAction),
     Close { hwnd: isize },
 }
 
+/// Workspace addressed by its position in the list of workspaces, or by its name
+pub enum WorkspaceTarget {
+    Index(usize),
+    Name(String),
+}
+
 pub enum WorkspaceAction {
     NextAsCurrent,
     PreviousAsCurrent,
     // SetCurrentWindow { hwnd: isize },
     ToggleMode(TilingMode),
-    PutCurrentWindowInWorkspace { workspace_index: usize },
+    FocusWorkspace(WorkspaceTarget),
+    ToggleFloating,
+    ToggleSticky,
+    SendCurrentToScratchpad {
+        name: String,
+    },
+    ToggleScratchpad {
+        name: String,
+    },
+    PutCurrentWindowInWorkspace(WorkspaceTarget),
+    MoveCurrentWorkspace(Direction),
+    SwapWorkspaces(Direction),
+    SetLayout(Layout),
+    ChangeMasterRatio(f32),
+    ChangeMasterCount(isize),
+    SwapCurrentWithMaster,
+    ResizeCurrent {
+        direction: TilingDirection,
+        delta: f32,
+    },
+    BalanceWeights,
+    SetContainerMode(ContainerMode),
+    SplitCurrent(TilingDirection),
+    MoveCurrentIntoSibling {
+        forward: bool,
+    },
+    MoveCurrentOutOfContainer,
+    FlattenCurrentContainer,
+    FocusDirection(Direction),
+    /// Focus the window focused before the current one, on any workspace
+    FocusLastWindow,
+    /// Walk back the focus history one window further at each call, like alt-tab
+    CycleFocusHistory,
+    SwapCurrentDirection(Direction),
+    MoveCurrentDirection(Direction),
 }
@@ -3,43 +3,99 @@ use std::{
     io::{self, BufRead},
 };
 
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 
-pub struct Config {
-    _excluded_windows: Vec<Regex>,
-    managed_windows: Vec<Regex>,
-    workspaces_monitors: Vec<String>,
+use crate::layout::{
+    Layout, LayoutOptions, MAX_COLUMN_WIDTH, MAX_MASTER_RATIO, MIN_COLUMN_WIDTH, MIN_MASTER_RATIO,
+};
+use crate::monitor::{Edge, Struts};
+use crate::rules::{
+    get_rule_effects, Rule, RuleCriteria, RuleEffect, RuleEffects, RuleMatching, WindowProperties,
+};
+use crate::windows::FloatingPlacement;
+
+/// Workspace declared by a `workspace` command
+#[derive(Debug, Clone)]
+pub struct WorkspaceConfig {
+    pub index: usize,
+    pub name: Option<String>,
+    pub monitor: String,
+    pub layout: Option<Layout>,
+    /// Monitors used in order when the main one is not connected
+    pub fallback_monitors: Vec<String>,
 }
-impl Config {
-    pub fn _is_excluded(&self, window_title: &str) -> bool {
-        for regex in self._excluded_windows.iter() {
-            if regex.is_match(&window_title) {
-                return true;
-            }
+impl WorkspaceConfig {
+    pub fn new(index: usize, monitor: &str) -> Self {
+        Self {
+            index,
+            name: None,
+            monitor: monitor.to_owned(),
+            layout: None,
+            fallback_monitors: Vec::new(),
         }
+    }
 
-        false
+    /// Get the name of the workspace, its index when it has none
+    pub fn get_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.index.to_string())
     }
 
-    pub fn is_managed(&self, window_title: &str) -> bool {
-        for regex in self.managed_windows.iter() {
-            if regex.is_match(&window_title) {
-                return true;
-            }
-        }
+    /// Get the monitors the workspace can be displayed on, by order of preference
+    pub fn get_monitors(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.monitor).chain(self.fallback_monitors.iter())
+    }
+}
 
-        false
+pub struct Config {
+    rules: Vec<Rule>,
+    rule_matching: RuleMatching,
+    workspaces: Vec<WorkspaceConfig>,
+    layout_options: LayoutOptions,
+    struts: Vec<(Edge, i32, Option<String>)>,
+}
+impl Config {
+    /// Get what the rules matching the window decide for it
+    pub fn get_rule_effects(&self, properties: &WindowProperties) -> RuleEffects {
+        get_rule_effects(&self.rules, self.rule_matching, properties)
     }
 
-    pub fn get_workspaces_monitors(&self) -> &Vec<String> {
-        &self.workspaces_monitors
+    pub fn get_workspaces(&self) -> &Vec<WorkspaceConfig> {
+        &self.workspaces
+    }
+
+    pub fn get_layout_options(&self) -> &LayoutOptions {
+        &self.layout_options
+    }
+
+    /// Get the struts of the monitor, the ones set for its name overriding the global ones
+    pub fn get_struts(&self, monitor_name: &str) -> Struts {
+        let mut struts = Struts::default();
+
+        let global_struts = self.struts.iter().filter(|strut| strut.2.is_none());
+        let monitor_struts = self
+            .struts
+            .iter()
+            .filter(|strut| strut.2.as_deref() == Some(monitor_name));
+        for (edge, size, _) in global_struts.chain(monitor_struts) {
+            struts.set(*edge, *size);
+        }
+
+        struts
     }
 }
 
 enum Command {
-    RuleExclude(Regex),
-    RuleManaged(Regex),
-    Workspace(usize, String),
+    Rule(Rule),
+    RuleMatching(RuleMatching),
+    Workspace(WorkspaceConfig),
+    Layout(Layout),
+    MasterRatio(f32),
+    MasterCount(usize),
+    ColumnWidth(f32),
+    InnerGap(i32),
+    OuterGap(i32),
+    Strut(Edge, i32, Option<String>),
+    FloatingPlacement(FloatingPlacement),
 }
 
 pub struct ConfigBuilder {
@@ -59,44 +115,113 @@ impl ConfigBuilder {
             std::fs::File::open(config_path).expect("Failed to open the configuration file");
 
         let lines = io::BufReader::new(config_file).lines();
-        for line in lines {
-            if let Ok(line) = line {
-                if line.is_empty() {
-                    continue;
-                }
+        for line in lines.map_while(Result::ok) {
+            if line.is_empty() {
+                continue;
+            }
 
-                let mut splitted_line: VecDeque<&str> = line.split(" ").collect();
+            let mut splitted_line: VecDeque<&str> = line.split(" ").collect();
 
-                if let Some(command) = splitted_line.pop_front() {
-                    match command {
-                        "workspace" => {
-                            if let Some(workspace_index) = splitted_line.pop_front() {
-                                let monitor_name = Vec::from(splitted_line).join(" ");
-                                if let Ok(workspace_index) = str::parse::<usize>(workspace_index) {
-                                    commands
-                                        .push(Command::Workspace(workspace_index, monitor_name));
-                                }
+            if let Some(command) = splitted_line.pop_front() {
+                match command {
+                    "workspace" => {
+                        if let Some(workspace_index) = splitted_line.pop_front() {
+                            if let Ok(workspace_index) = str::parse::<usize>(workspace_index) {
+                                commands.push(Command::Workspace(ConfigBuilder::parse_workspace(
+                                    workspace_index,
+                                    splitted_line,
+                                )));
+                            }
+                        }
+                    }
+                    "rule" => {
+                        if let Some(effect_names) = splitted_line.pop_front() {
+                            commands.push(Command::Rule(ConfigBuilder::parse_rule(
+                                effect_names,
+                                splitted_line,
+                            )));
+                        }
+                    }
+                    "rule_matching" => {
+                        if let Some(matching_name) = splitted_line.pop_front() {
+                            let matching =
+                                RuleMatching::from_name(matching_name).unwrap_or_else(|| {
+                                    panic!("Invalid rule matching {}", matching_name)
+                                });
+                            commands.push(Command::RuleMatching(matching));
+                        }
+                    }
+                    "layout" => {
+                        if let Some(layout_name) = splitted_line.pop_front() {
+                            let layout = Layout::from_name(layout_name)
+                                .unwrap_or_else(|| panic!("Invalid layout {}", layout_name));
+                            commands.push(Command::Layout(layout));
+                        }
+                    }
+                    "master_ratio" => {
+                        if let Some(ratio) = splitted_line.pop_front() {
+                            if let Ok(ratio) = str::parse::<f32>(ratio) {
+                                commands.push(Command::MasterRatio(ratio));
                             }
                         }
-                        "rule" => {
-                            if let Some(rule_command) = splitted_line.pop_front() {
-                                let remaining_line = Vec::from(splitted_line).join(" ");
-                                let regex = Regex::new(&remaining_line).expect(&format!(
-                                    "Could not compile the regex {}",
-                                    &remaining_line
-                                ));
-                                match rule_command {
-                                    "managed" => commands.push(Command::RuleManaged(regex)),
-                                    "exclude" => commands.push(Command::RuleExclude(regex)),
-                                    _ => panic!("Invalid rule subcommand"),
+                    }
+                    "master_count" => {
+                        if let Some(count) = splitted_line.pop_front() {
+                            if let Ok(count) = str::parse::<usize>(count) {
+                                commands.push(Command::MasterCount(count));
+                            }
+                        }
+                    }
+                    "column_width" => {
+                        if let Some(width) = splitted_line.pop_front() {
+                            if let Ok(width) = str::parse::<f32>(width) {
+                                commands.push(Command::ColumnWidth(width));
+                            }
+                        }
+                    }
+                    "gaps" => {
+                        if let (Some(gap_kind), Some(size)) =
+                            (splitted_line.pop_front(), splitted_line.pop_front())
+                        {
+                            if let Ok(size) = str::parse::<i32>(size) {
+                                match gap_kind {
+                                    "inner" => commands.push(Command::InnerGap(size)),
+                                    "outer" => commands.push(Command::OuterGap(size)),
+                                    _ => panic!("Invalid gaps subcommand"),
                                 }
                             }
                         }
-                        _ => panic!(
-                            "Error while parding the configuration, command {} not found",
-                            command
-                        ),
                     }
+                    "strut" => {
+                        if let (Some(edge_name), Some(size)) =
+                            (splitted_line.pop_front(), splitted_line.pop_front())
+                        {
+                            let edge = Edge::from_name(edge_name)
+                                .unwrap_or_else(|| panic!("Invalid strut edge {}", edge_name));
+                            let monitor_name = Vec::from(splitted_line).join(" ");
+                            let monitor_name = if monitor_name.is_empty() {
+                                None
+                            } else {
+                                Some(monitor_name)
+                            };
+                            if let Ok(size) = str::parse::<i32>(size) {
+                                commands.push(Command::Strut(edge, size, monitor_name));
+                            }
+                        }
+                    }
+                    "floating_placement" => {
+                        if let Some(placement_name) = splitted_line.pop_front() {
+                            let placement = FloatingPlacement::from_name(placement_name)
+                                .unwrap_or_else(|| {
+                                    panic!("Invalid floating placement {}", placement_name)
+                                });
+                            commands.push(Command::FloatingPlacement(placement));
+                        }
+                    }
+                    _ => panic!(
+                        "Error while parding the configuration, command {} not found",
+                        command
+                    ),
                 }
             }
         }
@@ -104,26 +229,199 @@ impl ConfigBuilder {
         commands
     }
 
+    /// Parse `<monitor name> [name=<name>] [layout=<layout>] [fallback=<monitor>,<monitor>]`
+    ///
+    /// The names may contain spaces, every word up to the next option belongs to the current value
+    fn parse_workspace(workspace_index: usize, arguments: VecDeque<&str>) -> WorkspaceConfig {
+        let (monitor_name, options) =
+            ConfigBuilder::parse_options(arguments, &["name", "layout", "fallback"]);
+
+        let mut workspace = WorkspaceConfig::new(workspace_index, &monitor_name);
+        for (key, value) in options {
+            match key {
+                "name" => workspace.name = Some(value),
+                "layout" => {
+                    let layout = Layout::from_name(&value)
+                        .unwrap_or_else(|| panic!("Invalid layout {}", value));
+                    workspace.layout = Some(layout);
+                }
+                _ => {
+                    workspace.fallback_monitors = value
+                        .split(',')
+                        .map(|monitor| monitor.trim().to_owned())
+                        .collect()
+                }
+            }
+        }
+
+        workspace
+    }
+
+    /// Parse `<effect>[,<effect>...] [title=<regex>] [class=<regex>] [exe=<regex>] [monitor=<name>]`
+    ///
+    /// The effects are separated by commas without spaces, so their values cannot contain any.
+    /// Like before the criteria existed, a bare regex is matched against the title.
+    /// The executable regex ignores the case, as the file names do on Windows.
+    /// Every effect but `exclude` makes the window managed
+    fn parse_rule(effect_names: &str, arguments: VecDeque<&str>) -> Rule {
+        let effects = effect_names
+            .split(',')
+            .map(|effect_name| {
+                RuleEffect::from_name(effect_name)
+                    .unwrap_or_else(|| panic!("Invalid rule effect {}", effect_name))
+            })
+            .collect();
+
+        let compile = |regex: &str| {
+            Regex::new(regex).unwrap_or_else(|_| panic!("Could not compile the regex {}", regex))
+        };
+
+        let (title, options) =
+            ConfigBuilder::parse_options(arguments, &["title", "class", "exe", "monitor"]);
+        let mut criteria = RuleCriteria::default();
+        if !title.is_empty() {
+            criteria.title = Some(compile(&title));
+        }
+        for (key, value) in options {
+            match key {
+                "title" => criteria.title = Some(compile(&value)),
+                "class" => criteria.class = Some(compile(&value)),
+                "exe" => {
+                    let regex = RegexBuilder::new(&value)
+                        .case_insensitive(true)
+                        .build()
+                        .unwrap_or_else(|_| panic!("Could not compile the regex {}", value));
+                    criteria.executable = Some(regex);
+                }
+                _ => criteria.monitor = Some(value),
+            }
+        }
+
+        Rule { criteria, effects }
+    }
+
+    /// Split the arguments into the words preceding the first known `<key>=` and the options
+    fn parse_options<'a>(
+        arguments: VecDeque<&str>,
+        keys: &[&'a str],
+    ) -> (String, Vec<(&'a str, String)>) {
+        let mut leading_words = Vec::new();
+        let mut options: Vec<(&'a str, Vec<&str>)> = Vec::new();
+        for argument in arguments {
+            let key = argument
+                .split_once('=')
+                .and_then(|(key, value)| Some((*keys.iter().find(|known| **known == key)?, value)));
+            match key {
+                Some((key, value)) => options.push((key, vec![value])),
+                None => match options.last_mut() {
+                    Some((_, value)) => value.push(argument),
+                    None => leading_words.push(argument),
+                },
+            }
+        }
+
+        let options = options
+            .into_iter()
+            .map(|(key, value)| (key, value.join(" ")))
+            .collect();
+
+        (leading_words.join(" "), options)
+    }
+
     pub fn build(&self) -> Config {
-        let mut managed_rule_regexes = Vec::new();
-        let mut unmanaged_rule_regexes: Vec<Regex> = Vec::new();
-        let mut workspaces: Vec<(usize, String)> = Vec::new();
+        let mut rules = Vec::new();
+        let mut rule_matching = RuleMatching::default();
+        let mut workspaces: Vec<WorkspaceConfig> = Vec::new();
+        let mut layout_options = LayoutOptions::default();
+        let mut struts = Vec::new();
 
         for command in self.commands.iter() {
             match command {
-                Command::RuleExclude(regex) => unmanaged_rule_regexes.push(regex.clone()),
-                Command::RuleManaged(regex) => managed_rule_regexes.push(regex.clone()),
-                Command::Workspace(index, name) => workspaces.push((index.clone(), name.clone())),
+                Command::Rule(rule) => rules.push(rule.clone()),
+                Command::RuleMatching(matching) => rule_matching = *matching,
+                Command::Workspace(workspace) => workspaces.push(workspace.clone()),
+                Command::Layout(layout) => layout_options.layout = *layout,
+                Command::MasterRatio(ratio) => {
+                    layout_options.master_ratio = ratio.clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO)
+                }
+                Command::MasterCount(count) => layout_options.master_count = *count,
+                Command::ColumnWidth(width) => {
+                    layout_options.column_width = width.clamp(MIN_COLUMN_WIDTH, MAX_COLUMN_WIDTH)
+                }
+                Command::InnerGap(size) => layout_options.inner_gap = (*size).max(0),
+                Command::OuterGap(size) => layout_options.outer_gap = (*size).max(0),
+                Command::Strut(edge, size, monitor_name) => {
+                    struts.push((*edge, *size, monitor_name.clone()))
+                }
+                Command::FloatingPlacement(placement) => {
+                    layout_options.floating_placement = *placement
+                }
             }
         }
 
         // Sort the workspaces by indexes
-        workspaces.sort_by(|a, b| a.0.cmp(&b.0));
+        workspaces.sort_by_key(|workspace| workspace.index);
 
         Config {
-            _excluded_windows: unmanaged_rule_regexes,
-            managed_windows: managed_rule_regexes,
-            workspaces_monitors: workspaces.into_iter().map(|item| item.1).collect(),
+            rules,
+            rule_matching,
+            workspaces,
+            layout_options,
+            struts,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_rule(line: &str) -> Rule {
+        let mut arguments: VecDeque<&str> = line.split(' ').collect();
+        let effect_names = arguments.pop_front().unwrap();
+        ConfigBuilder::parse_rule(effect_names, arguments)
+    }
+
+    #[test]
+    fn options_follow_the_leading_words() {
+        let arguments: VecDeque<&str> = "main screen name=web browser layout=grid"
+            .split(' ')
+            .collect();
+        let (leading_words, options) = ConfigBuilder::parse_options(arguments, &["name", "layout"]);
+
+        assert_eq!(leading_words, "main screen");
+        assert_eq!(
+            options,
+            vec![
+                ("name", "web browser".to_owned()),
+                ("layout", "grid".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn rule_criteria_are_parsed() {
+        let rule = parse_rule("float,sticky ^Bare title$ class=^Term$ exe=TERM.EXE monitor=main");
+
+        assert_eq!(
+            rule.effects,
+            vec![RuleEffect::Float, RuleEffect::Sticky { tiled: false }]
+        );
+        assert_eq!(rule.criteria.title.unwrap().as_str(), "^Bare title$");
+        assert_eq!(rule.criteria.class.unwrap().as_str(), "^Term$");
+        assert!(rule.criteria.executable.unwrap().is_match("term.exe"));
+        assert_eq!(rule.criteria.monitor.as_deref(), Some("main"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Could not compile the regex")]
+    fn invalid_regex_is_rejected() {
+        parse_rule("managed title=(unclosed");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid rule effect")]
+    fn invalid_effect_is_rejected() {
+        parse_rule("managed,unknown title=.*");
+    }
+}
@@ -0,0 +1,21 @@
+/// Change of a top-level window reported by the window system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowEvent {
+    /// The window appeared, it is also sent when a window is shown or restored
+    Created(isize),
+    /// The window was closed or hidden
+    Destroyed(isize),
+    TitleChanged(isize),
+    MovedOrResized(isize),
+    /// The window became the foreground window
+    FocusChanged(isize),
+    Minimized(isize),
+}
+
+pub type WindowEventCallback = Box<dyn FnMut(WindowEvent) + Send>;
+
+/// Producer of the window events
+pub trait WindowEventSource {
+    /// Deliver the events to the callback as they happen, blocking the calling thread
+    fn listen(&self, callback: WindowEventCallback);
+}
@@ -4,7 +4,10 @@ use windows_sys::Win32::{
     Foundation::{LPARAM, LRESULT, WPARAM},
     System::LibraryLoader::GetModuleHandleW,
     UI::{
-        Input::KeyboardAndMouse::{RegisterHotKey, UnregisterHotKey, MOD_CONTROL, MOD_SHIFT, VK_1},
+        Input::KeyboardAndMouse::{
+            RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_SHIFT,
+            MOD_WIN, VIRTUAL_KEY, VK_1, VK_7, VK_LEFT,
+        },
         WindowsAndMessaging::{
             CreateWindowExW, DefWindowProcW, DestroyWindow, GetWindowLongPtrW, PostMessageW,
             RegisterClassW, SetWindowLongPtrA, CS_HREDRAW, CS_VREDRAW, GWLP_USERDATA, WM_CLOSE,
@@ -13,12 +16,20 @@ use windows_sys::Win32::{
     },
 };
 
-use crate::{
-    actions::{WmAction, WorkspaceAction},
+use wwm::{
+    actions::{WmAction, WorkspaceAction, WorkspaceTarget},
+    layout::Layout,
+    monitor::Direction,
+    scratchpad::DEFAULT_SCRATCHPAD,
+    tree::{ContainerMode, TilingDirection},
     windows::TilingMode,
-    GlobalWindowData,
 };
 
+use crate::{GlobalWindowData, Message};
+
+const MASTER_RATIO_STEP: f32 = 0.05;
+const RESIZE_STEP: f32 = 0.1;
+
 pub fn create_inputs_window(global_data: Box<GlobalWindowData>) -> isize {
     let h_instance = unsafe { GetModuleHandleW(std::ptr::null()) };
     let name = CString::new("wWm").unwrap();
@@ -60,40 +71,260 @@ fn handle_hotkey(hwnd: isize, key: u16) {
         unsafe { GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut GlobalWindowData };
     let window_data = unsafe { &*window_data_ptr };
     match key {
-        1 => dispatch(
+        1..=9 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::FocusWorkspace(WorkspaceTarget::Index(
+                key as usize - 1,
+            ))),
+        ),
+        10..=18 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::PutCurrentWindowInWorkspace(
+                WorkspaceTarget::Index(key as usize - 10),
+            )),
+        ),
+        19 => dispatch(
             window_data,
             WmAction::Workspace(WorkspaceAction::PreviousAsCurrent),
         ),
-        2 => dispatch(
+        20 => dispatch(
             window_data,
             WmAction::Workspace(WorkspaceAction::NextAsCurrent),
         ),
-        3 => dispatch(
+        21 => dispatch(
             window_data,
             WmAction::Workspace(WorkspaceAction::ToggleMode(TilingMode::Monocle)),
         ),
-        4 => dispatch(
+        22 => dispatch(
             window_data,
             WmAction::Workspace(WorkspaceAction::ToggleMode(TilingMode::Managed)),
         ),
-        9 => {
+        23 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetLayout(Layout::MasterStack)),
+        ),
+        24 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetLayout(Layout::Tiling)),
+        ),
+        25 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapCurrentWithMaster),
+        ),
+        26 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetLayout(Layout::Dwindle)),
+        ),
+        27 => {
             window_data
                 .sender
-                .send(crate::actions::WmAction::Close { hwnd })
+                .send(Message::Action(WmAction::Close { hwnd }))
                 .unwrap();
         }
-        10 => dispatch(
+        28 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ChangeMasterRatio(MASTER_RATIO_STEP)),
+        ),
+        29 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ChangeMasterRatio(-MASTER_RATIO_STEP)),
+        ),
+        30 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ChangeMasterCount(1)),
+        ),
+        31 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ChangeMasterCount(-1)),
+        ),
+        32 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetLayout(Layout::Spiral)),
+        ),
+        33 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetLayout(Layout::Grid)),
+        ),
+        34 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetLayout(Layout::Scrolling)),
+        ),
+        35 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ResizeCurrent {
+                direction: TilingDirection::Horizontal,
+                delta: RESIZE_STEP,
+            }),
+        ),
+        36 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ResizeCurrent {
+                direction: TilingDirection::Horizontal,
+                delta: -RESIZE_STEP,
+            }),
+        ),
+        37 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ResizeCurrent {
+                direction: TilingDirection::Vertical,
+                delta: RESIZE_STEP,
+            }),
+        ),
+        38 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ResizeCurrent {
+                direction: TilingDirection::Vertical,
+                delta: -RESIZE_STEP,
+            }),
+        ),
+        39 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::BalanceWeights),
+        ),
+        40 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetContainerMode(ContainerMode::Tabbed)),
+        ),
+        41 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetContainerMode(ContainerMode::Stacked)),
+        ),
+        42 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SetContainerMode(ContainerMode::Split)),
+        ),
+        43 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SplitCurrent(TilingDirection::Vertical)),
+        ),
+        44 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SplitCurrent(TilingDirection::Horizontal)),
+        ),
+        45 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentIntoSibling { forward: false }),
+        ),
+        46 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentIntoSibling { forward: true }),
+        ),
+        47 => dispatch(
             window_data,
-            WmAction::Workspace(WorkspaceAction::PutCurrentWindowInWorkspace {
-                workspace_index: 0,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentOutOfContainer),
+        ),
+        48 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::FlattenCurrentContainer),
+        ),
+        49 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ToggleFloating),
+        ),
+        50 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SendCurrentToScratchpad {
+                name: DEFAULT_SCRATCHPAD.to_owned(),
             }),
         ),
-        11 => dispatch(
+        51 => dispatch(
             window_data,
-            WmAction::Workspace(WorkspaceAction::PutCurrentWindowInWorkspace {
-                workspace_index: 1,
+            WmAction::Workspace(WorkspaceAction::ToggleScratchpad {
+                name: DEFAULT_SCRATCHPAD.to_owned(),
             }),
         ),
+        52 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::FocusDirection(Direction::Left)),
+        ),
+        53 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::FocusDirection(Direction::Up)),
+        ),
+        54 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::FocusDirection(Direction::Right)),
+        ),
+        55 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::FocusDirection(Direction::Down)),
+        ),
+        56 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapCurrentDirection(Direction::Left)),
+        ),
+        57 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapCurrentDirection(Direction::Up)),
+        ),
+        58 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapCurrentDirection(Direction::Right)),
+        ),
+        59 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapCurrentDirection(Direction::Down)),
+        ),
+        60 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentDirection(Direction::Left)),
+        ),
+        61 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentDirection(Direction::Up)),
+        ),
+        62 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentDirection(Direction::Right)),
+        ),
+        63 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentDirection(Direction::Down)),
+        ),
+        64 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentWorkspace(Direction::Left)),
+        ),
+        65 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentWorkspace(Direction::Up)),
+        ),
+        66 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentWorkspace(Direction::Right)),
+        ),
+        67 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::MoveCurrentWorkspace(Direction::Down)),
+        ),
+        68 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapWorkspaces(Direction::Left)),
+        ),
+        69 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapWorkspaces(Direction::Up)),
+        ),
+        70 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapWorkspaces(Direction::Right)),
+        ),
+        71 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::SwapWorkspaces(Direction::Down)),
+        ),
+        72 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::ToggleSticky),
+        ),
+        73 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::FocusLastWindow),
+        ),
+        74 => dispatch(
+            window_data,
+            WmAction::Workspace(WorkspaceAction::CycleFocusHistory),
+        ),
         _ => {}
     }
 }
@@ -101,7 +332,7 @@ fn handle_hotkey(hwnd: isize, key: u16) {
 fn dispatch(window_data: &GlobalWindowData, action: WmAction) {
     window_data
         .sender
-        .send(action)
+        .send(Message::Action(action))
         .expect("Could not dispatch the Window manager action ")
 }
 
@@ -126,47 +357,59 @@ unsafe extern "system" fn window_proc(
         WM_CLOSE => {
             unregister_hotkeys();
             DestroyWindow(hwnd);
-            return 0;
+            0
         }
         WM_HOTKEY => {
             handle_hotkey(hwnd, wparam as u16);
-            return 0;
+            0
         }
-        _ => return DefWindowProcW(hwnd, msg, wparam, lparam),
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
     }
 }
 
 pub fn register_hotkeys() {
-    let modifier = MOD_CONTROL;
-    for hotkey_index in 0..9 {
-        let registered = unsafe {
-            RegisterHotKey(
-                0,
-                hotkey_index + 1,
-                modifier,
-                VK_1 as u32 + hotkey_index as u32,
-            )
-        }; // VK_1
-        println!("Hotkey {} registered: {}", hotkey_index + 1, registered);
-    }
+    // Workspaces
+    register_hotkeys_row(MOD_CONTROL, 1, VK_1, 9);
+    register_hotkeys_row(MOD_CONTROL | MOD_SHIFT, 10, VK_1, 9);
+
+    // Windows and layouts
+    register_hotkeys_row(MOD_CONTROL | MOD_ALT | MOD_SHIFT, 19, VK_1, 9);
+    register_hotkeys_row(MOD_ALT, 28, VK_1, 6);
+    register_hotkeys_row(MOD_ALT, 72, VK_7, 3);
+    register_hotkeys_row(MOD_CONTROL | MOD_ALT, 34, VK_1, 9);
+    register_hotkeys_row(MOD_ALT | MOD_SHIFT, 43, VK_1, 9);
 
-    let modifier = MOD_SHIFT | MOD_CONTROL;
-    for hotkey_index in 1..9 {
-        let registered = unsafe {
-            RegisterHotKey(
-                0,
-                hotkey_index + 9,
-                modifier,
-                VK_1 as u32 + (hotkey_index - 1) as u32,
-            )
-        }; // VK_1
-        println!("Hotkey {} registered: {}", hotkey_index + 9, registered);
+    // The arrow keys follow each other: left, up, right, down
+    // Windows keeps Win+Shift+arrows and the graphics drivers Ctrl+Alt+arrows, so Win+Alt is the base
+    register_hotkeys_row(MOD_WIN | MOD_ALT, 52, VK_LEFT, 4);
+    register_hotkeys_row(MOD_ALT | MOD_SHIFT, 56, VK_LEFT, 4);
+    register_hotkeys_row(MOD_WIN | MOD_ALT | MOD_CONTROL, 60, VK_LEFT, 4);
+    register_hotkeys_row(MOD_WIN | MOD_ALT | MOD_SHIFT, 64, VK_LEFT, 4);
+    register_hotkeys_row(MOD_CONTROL | MOD_ALT | MOD_SHIFT, 68, VK_LEFT, 4);
+}
+
+/// Register the hotkeys of the consecutive keys starting at `first_key`, with consecutive ids
+fn register_hotkeys_row(
+    modifier: HOT_KEY_MODIFIERS,
+    first_id: i32,
+    first_key: VIRTUAL_KEY,
+    count: i32,
+) {
+    for hotkey_index in 0..count {
+        let id = first_id + hotkey_index;
+        let registered =
+            unsafe { RegisterHotKey(0, id, modifier, first_key as u32 + hotkey_index as u32) };
+        if registered == 0 {
+            eprintln!("Could not register the hotkey {}, it is already used !", id);
+        } else {
+            println!("Hotkey {} registered", id);
+        }
     }
 }
 
 pub fn unregister_hotkeys() {
     println!("Unregistering the hotkeys");
-    for hotkey_index in 0..18 {
+    for hotkey_index in 0..74 {
         unsafe { UnregisterHotKey(0, hotkey_index + 1) };
     }
 }
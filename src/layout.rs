@@ -0,0 +1,727 @@
+use std::collections::HashMap;
+
+use crate::{
+    monitor::Rect,
+    platform::WindowSystem,
+    tree::{ContainerMode, Node, TilingDirection},
+    windows::{FloatingPlacement, TilingMode, Window},
+};
+
+/// Position of every window of a tree, in the order they have to be applied
+pub type LayoutPlan = Vec<(isize, Rect)>;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Layout {
+    /// Split the windows evenly following the direction of the tree nodes
+    Tiling,
+    /// Master windows on the left of the monitor, the other ones stacked on the right
+    MasterStack,
+    /// Every new window splits the current window along its longer side
+    Dwindle,
+    /// Every new window splits the current window, rotating the split direction each time
+    Spiral,
+    /// Windows in a near-square matrix of ceil(sqrt(n)) columns
+    Grid,
+    /// Every window gets a column on a horizontal strip scrolling to show the current window
+    Scrolling,
+}
+impl Layout {
+    /// Whether the new windows are nested in the tree by splitting the current one
+    pub fn is_binary_space_partition(&self) -> bool {
+        matches!(self, Layout::Dwindle | Layout::Spiral)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tiling" => Some(Layout::Tiling),
+            "master_stack" => Some(Layout::MasterStack),
+            "dwindle" => Some(Layout::Dwindle),
+            "spiral" => Some(Layout::Spiral),
+            "grid" => Some(Layout::Grid),
+            "scrolling" => Some(Layout::Scrolling),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LayoutOptions {
+    pub layout: Layout,
+    /// Share of the monitor width taken by the master windows
+    pub master_ratio: f32,
+    pub master_count: usize,
+    /// Share of the monitor width taken by a column of the scrolling layout
+    pub column_width: f32,
+    /// Space between two windows
+    pub inner_gap: i32,
+    /// Space between the windows and the edges of the monitor
+    pub outer_gap: i32,
+    pub floating_placement: FloatingPlacement,
+}
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            layout: Layout::Tiling,
+            master_ratio: 0.55,
+            master_count: 1,
+            column_width: 0.5,
+            inner_gap: 0,
+            outer_gap: 0,
+            floating_placement: FloatingPlacement::Center,
+        }
+    }
+}
+impl LayoutOptions {
+    pub fn get_column_width(&self, area: &Rect) -> i32 {
+        (area.width() as f32 * self.column_width) as i32
+    }
+
+    /// Get the area split between the windows, before removing the inner gaps around each of them
+    ///
+    /// Each window is shrunk by half the inner gap, so the area is grown by the same amount to keep
+    /// the outer gap at the edges of the monitor
+    pub fn get_layout_area(&self, monitor_rect: &Rect) -> Rect {
+        let (before, after) = self.get_inner_gap_halves();
+        monitor_rect.inset(
+            self.outer_gap - before,
+            self.outer_gap - before,
+            self.outer_gap - after,
+            self.outer_gap - after,
+        )
+    }
+
+    fn get_inner_gap_halves(&self) -> (i32, i32) {
+        let before = self.inner_gap / 2;
+        (before, self.inner_gap - before)
+    }
+}
+
+pub const MIN_MASTER_RATIO: f32 = 0.1;
+pub const MAX_MASTER_RATIO: f32 = 0.9;
+pub const MIN_COLUMN_WIDTH: f32 = 0.1;
+pub const MAX_COLUMN_WIDTH: f32 = 1.0;
+
+/// Position where the windows outside of the visible area are moved, far from every monitor
+pub const PARKING_POSITION: i32 = -32000;
+
+/// Compute where every window of the tree has to be placed on the monitor, without moving them
+///
+/// The monitor rect is the area available for the windows, the gaps are taken from it. The scroll offset is the position of the monitor on the strip of the scrolling layout
+pub fn compute_layout(
+    windows: &Node<Window>,
+    monitor_rect: &Rect,
+    options: &LayoutOptions,
+    scroll_offset: i32,
+) -> LayoutPlan {
+    let area = options.get_layout_area(monitor_rect);
+
+    // A tabbed or stacked root shows a single window whatever the layout is
+    let layout = if windows.container_mode == ContainerMode::Split {
+        options.layout
+    } else {
+        Layout::Tiling
+    };
+
+    let mut plan = Vec::new();
+    match layout {
+        Layout::Tiling | Layout::Dwindle | Layout::Spiral => {
+            compute_recursive(windows, &area, &area, &mut plan)
+        }
+        Layout::MasterStack => compute_master_stack(windows, &area, options, &mut plan),
+        Layout::Grid => compute_grid(windows, &area, &mut plan),
+        Layout::Scrolling => compute_scrolling(windows, &area, options, scroll_offset, &mut plan),
+    }
+
+    // The parked windows stay exactly at the parking position, so they are still recognised
+    let (before, after) = options.get_inner_gap_halves();
+    for (_, rect) in plan.iter_mut().filter(|(_, rect)| !is_parked_rect(rect)) {
+        *rect = rect.inset(before, before, after, after);
+    }
+
+    plan
+}
+
+/// Whether the window is minimised, or the container only holds minimised windows
+fn is_minimized(node: &Node<Window>) -> bool {
+    if node.is_leaf() {
+        return node.value.minimized;
+    }
+
+    node.childrens.iter().all(|children| is_minimized(children))
+}
+
+fn count_managed_childrens(windows: &Node<Window>) -> usize {
+    windows
+        .childrens
+        .iter()
+        .filter(|item| item.value.mode == TilingMode::Managed && !is_minimized(item))
+        .count()
+}
+
+/// Place the managed root childrens in the slots, in order, and the monocle ones on the whole monitor
+fn place_in_slots(
+    windows: &Node<Window>,
+    slots: Vec<Rect>,
+    monitor_rect: &Rect,
+    plan: &mut LayoutPlan,
+) {
+    let mut slots = slots.into_iter();
+    for children in windows.childrens.iter().filter(|item| !is_minimized(item)) {
+        let slot = match children.value.mode {
+            TilingMode::Managed => slots.next().unwrap_or(*monitor_rect),
+            TilingMode::Monocle => *monitor_rect,
+            TilingMode::Floating => continue,
+        };
+
+        if children.is_leaf() {
+            plan.push((children.value.hwnd, slot));
+        } else {
+            compute_recursive(children, &slot, monitor_rect, plan);
+        }
+    }
+}
+
+fn compute_master_stack(
+    windows: &Node<Window>,
+    monitor_rect: &Rect,
+    options: &LayoutOptions,
+    plan: &mut LayoutPlan,
+) {
+    let managed_childrens_count = count_managed_childrens(windows);
+    let master_count = options.master_count.min(managed_childrens_count);
+    let stack_count = managed_childrens_count - master_count;
+
+    let master_width = if stack_count == 0 {
+        monitor_rect.width()
+    } else if master_count == 0 {
+        0
+    } else {
+        (monitor_rect.width() as f32 * options.master_ratio) as i32
+    };
+    let master_rects = split_rect(
+        &Rect::new(
+            monitor_rect.left,
+            monitor_rect.top,
+            monitor_rect.left + master_width,
+            monitor_rect.bottom,
+        ),
+        master_count,
+        &TilingDirection::Vertical,
+    );
+    let stack_rects = split_rect(
+        &Rect::new(
+            monitor_rect.left + master_width,
+            monitor_rect.top,
+            monitor_rect.right,
+            monitor_rect.bottom,
+        ),
+        stack_count,
+        &TilingDirection::Vertical,
+    );
+
+    let slots = master_rects.into_iter().chain(stack_rects).collect();
+    place_in_slots(windows, slots, monitor_rect, plan);
+}
+
+fn compute_grid(windows: &Node<Window>, monitor_rect: &Rect, plan: &mut LayoutPlan) {
+    let managed_childrens_count = count_managed_childrens(windows);
+    let columns_count = (managed_childrens_count as f64).sqrt().ceil() as usize;
+    let rows_count = managed_childrens_count.div_ceil(columns_count.max(1));
+
+    // Every row is full except the last one, whose windows share the whole monitor width
+    let rows = split_rect(monitor_rect, rows_count, &TilingDirection::Vertical);
+    let last_row_count = managed_childrens_count - columns_count * rows_count.saturating_sub(1);
+    let slots = rows
+        .iter()
+        .enumerate()
+        .flat_map(|(index, row)| {
+            let row_count = if index + 1 == rows_count {
+                last_row_count
+            } else {
+                columns_count
+            };
+            split_rect(row, row_count, &TilingDirection::Horizontal)
+        })
+        .collect();
+
+    place_in_slots(windows, slots, monitor_rect, plan);
+}
+
+fn compute_scrolling(
+    windows: &Node<Window>,
+    monitor_rect: &Rect,
+    options: &LayoutOptions,
+    scroll_offset: i32,
+    plan: &mut LayoutPlan,
+) {
+    let column_width = options.get_column_width(monitor_rect);
+    let managed_childrens_count = count_managed_childrens(windows) as i32;
+
+    // The columns only partially on the monitor would overflow on the neighbour ones, park them too
+    let slots = (0..managed_childrens_count)
+        .map(|index| {
+            let left = monitor_rect.left + index * column_width - scroll_offset;
+            let right = left + column_width;
+            let column_rect = Rect::new(left, monitor_rect.top, right, monitor_rect.bottom);
+            if left >= monitor_rect.left && right <= monitor_rect.right {
+                column_rect
+            } else {
+                get_parked_rect(&column_rect)
+            }
+        })
+        .collect();
+
+    place_in_slots(windows, slots, monitor_rect, plan);
+}
+
+/// Get a rect of the same size far from every monitor
+pub fn get_parked_rect(rect: &Rect) -> Rect {
+    Rect::new(
+        PARKING_POSITION,
+        PARKING_POSITION,
+        PARKING_POSITION + rect.width(),
+        PARKING_POSITION + rect.height(),
+    )
+}
+
+pub fn is_parked_rect(rect: &Rect) -> bool {
+    rect.left == PARKING_POSITION && rect.top == PARKING_POSITION
+}
+
+/// Split the rect in `count` equal parts placed one after the other in the given direction
+fn split_rect(rect: &Rect, count: usize, direction: &TilingDirection) -> Vec<Rect> {
+    split_rect_weighted(rect, &vec![1.0; count], direction)
+}
+
+/// Split the rect in parts proportional to the weights, placed one after the other in the given direction
+fn split_rect_weighted(rect: &Rect, weights: &[f32], direction: &TilingDirection) -> Vec<Rect> {
+    let length = match direction {
+        TilingDirection::Vertical => rect.height(),
+        TilingDirection::Horizontal => rect.width(),
+    };
+
+    distribute(length, weights)
+        .windows(2)
+        .map(|bounds| match direction {
+            TilingDirection::Vertical => Rect::new(
+                rect.left,
+                rect.top + bounds[0],
+                rect.right,
+                rect.top + bounds[1],
+            ),
+            TilingDirection::Horizontal => Rect::new(
+                rect.left + bounds[0],
+                rect.top,
+                rect.left + bounds[1],
+                rect.bottom,
+            ),
+        })
+        .collect()
+}
+
+/// Get the bounds of the parts of a length split proportionally to the weights
+///
+/// The bounds are rounded from the cumulated weights, so the parts always add up to the whole length
+fn distribute(length: i32, weights: &[f32]) -> Vec<i32> {
+    let total_weight: f32 = weights.iter().sum();
+    if weights.is_empty() || total_weight <= 0.0 {
+        return vec![0; weights.len() + 1];
+    }
+
+    let mut bounds = vec![0];
+    let mut cumulated_weight = 0.0;
+    for weight in weights.iter() {
+        cumulated_weight += weight;
+        bounds.push((length as f32 * cumulated_weight / total_weight).round() as i32);
+    }
+
+    bounds
+}
+
+fn compute_recursive(
+    current_node: &Node<Window>,
+    rect: &Rect,
+    monitor_rect: &Rect,
+    plan: &mut LayoutPlan,
+) {
+    if current_node.is_leaf() {
+        return;
+    }
+
+    if current_node.container_mode != ContainerMode::Split {
+        compute_single_visible(current_node, rect, monitor_rect, plan);
+        return;
+    }
+
+    let weights: Vec<f32> = current_node
+        .childrens
+        .iter()
+        .filter(|item| item.value.mode == TilingMode::Managed && !is_minimized(item))
+        .map(|item| item.weight)
+        .collect();
+    let widths = distribute(rect.width(), &weights);
+    let heights = distribute(rect.height(), &weights);
+
+    let mut child_x = rect.left;
+    let mut child_y = rect.top;
+    let mut managed_index = 0;
+
+    for children in current_node
+        .childrens
+        .iter()
+        .filter(|item| !is_minimized(item))
+    {
+        // The monocle containers take the place of the next managed window without moving it
+        let slot_index = managed_index.min(weights.len().saturating_sub(1));
+        let child_width = match children.direction {
+            TilingDirection::Horizontal if !weights.is_empty() => {
+                widths[slot_index + 1] - widths[slot_index]
+            }
+            _ => rect.width(),
+        };
+        let child_height = match children.direction {
+            TilingDirection::Vertical if !weights.is_empty() => {
+                heights[slot_index + 1] - heights[slot_index]
+            }
+            _ => rect.height(),
+        };
+        let child_rect = Rect::new(
+            child_x,
+            child_y,
+            child_x + child_width,
+            child_y + child_height,
+        );
+
+        match children.value.mode {
+            TilingMode::Managed => {
+                if children.is_leaf() {
+                    plan.push((children.value.hwnd, child_rect));
+                } else {
+                    compute_recursive(children, &child_rect, monitor_rect, plan);
+                }
+
+                match children.direction {
+                    TilingDirection::Vertical => child_y += child_height,
+                    TilingDirection::Horizontal => child_x += child_width,
+                }
+                managed_index += 1;
+            }
+            TilingMode::Monocle => {
+                if children.is_leaf() {
+                    plan.push((children.value.hwnd, *monitor_rect));
+                } else {
+                    compute_recursive(children, &child_rect, monitor_rect, plan);
+                }
+            }
+            TilingMode::Floating => {}
+        }
+    }
+}
+
+/// Give the whole rect to the active children of a tabbed or stacked container
+fn compute_single_visible(
+    current_node: &Node<Window>,
+    rect: &Rect,
+    monitor_rect: &Rect,
+    plan: &mut LayoutPlan,
+) {
+    let active_index = current_node
+        .active_children
+        .min(current_node.childrens.len() - 1);
+
+    for (index, children) in current_node.childrens.iter().enumerate() {
+        if is_minimized(children) {
+            continue;
+        }
+
+        let child_rect =
+            if index == active_index || current_node.container_mode == ContainerMode::Stacked {
+                *rect
+            } else {
+                get_parked_rect(rect)
+            };
+
+        if children.is_leaf() {
+            let child_rect = match children.value.mode {
+                TilingMode::Managed => child_rect,
+                TilingMode::Monocle => *monitor_rect,
+                TilingMode::Floating => continue,
+            };
+            plan.push((children.value.hwnd, child_rect));
+        } else {
+            compute_recursive(children, &child_rect, monitor_rect, plan);
+        }
+    }
+}
+
+/// Push layout plans to the window system, skipping the windows that are already in place
+#[derive(Default)]
+pub struct LayoutApplier {
+    applied: HashMap<isize, Rect>,
+}
+impl LayoutApplier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply<S: WindowSystem>(&mut self, system: &S, plan: &LayoutPlan) {
+        for (hwnd, rect) in plan.iter() {
+            if self.applied.get(hwnd) == Some(rect) {
+                continue;
+            }
+
+            if system.set_window_pos(*hwnd, rect.left, rect.top, rect.width(), rect.height()) {
+                self.applied.insert(*hwnd, *rect);
+            }
+        }
+
+        self.applied
+            .retain(|hwnd, _| plan.iter().any(|(planned, _)| planned == hwnd));
+    }
+
+    /// Forget the window if it left the position given by the last plan, so the next one puts it back
+    pub fn forget_if_moved<S: WindowSystem>(&mut self, system: &S, hwnd: isize) {
+        if let Some(rect) = self.applied.get(&hwnd) {
+            if system.get_window_rect(hwnd).as_ref() != Some(rect) {
+                self.applied.remove(&hwnd);
+            }
+        }
+    }
+
+    /// Forget the position of every window so the next plan is fully applied
+    pub fn invalidate(&mut self) {
+        self.applied.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::WindowEvent;
+    use crate::monitor::{Edge, Monitor, Struts};
+    use crate::platform::fake::FakeWindowSystem;
+
+    /// Create a root holding one leaf per handle, side by side
+    fn create_tree(hwnds: &[isize]) -> Node<Window> {
+        let mut root = Node::new(Window::container(), TilingDirection::Horizontal);
+        for (index, hwnd) in hwnds.iter().enumerate() {
+            root.insert(
+                Window::new(&hwnd.to_string(), *hwnd),
+                index,
+                TilingDirection::Horizontal,
+            );
+        }
+
+        root
+    }
+
+    fn get_rects(plan: &LayoutPlan) -> Vec<Rect> {
+        plan.iter().map(|(_, rect)| *rect).collect()
+    }
+
+    #[test]
+    fn distribute_gives_the_remainder_without_losing_a_pixel() {
+        assert_eq!(distribute(1001, &[1.0, 1.0, 1.0]), vec![0, 334, 667, 1001]);
+        assert_eq!(distribute(10, &[1.0; 4]), vec![0, 3, 5, 8, 10]);
+        assert_eq!(distribute(100, &[]), vec![0]);
+    }
+
+    #[test]
+    fn tiling_splits_an_odd_width_into_adjacent_rects() {
+        let monitor_rect = Rect::new(0, 0, 1001, 600);
+        let plan = compute_layout(
+            &create_tree(&[1, 2, 3]),
+            &monitor_rect,
+            &LayoutOptions::default(),
+            0,
+        );
+
+        assert_eq!(
+            get_rects(&plan),
+            vec![
+                Rect::new(0, 0, 334, 600),
+                Rect::new(334, 0, 667, 600),
+                Rect::new(667, 0, 1001, 600),
+            ]
+        );
+    }
+
+    #[test]
+    fn gaps_surround_and_separate_the_windows() {
+        let options = LayoutOptions {
+            inner_gap: 10,
+            outer_gap: 20,
+            ..LayoutOptions::default()
+        };
+        let plan = compute_layout(
+            &create_tree(&[1, 2]),
+            &Rect::new(0, 0, 1000, 600),
+            &options,
+            0,
+        );
+
+        assert_eq!(
+            get_rects(&plan),
+            vec![Rect::new(20, 20, 495, 580), Rect::new(505, 20, 980, 580)]
+        );
+    }
+
+    #[test]
+    fn struts_are_kept_out_of_the_layout() {
+        let mut monitor = Monitor::new("main", 1, Rect::new(0, 0, 1000, 600));
+        let mut struts = Struts::default();
+        struts.set(Edge::Top, 30);
+        struts.set(Edge::Left, 100);
+        monitor.reserve_struts(&struts);
+
+        let plan = compute_layout(
+            &create_tree(&[1]),
+            &monitor.work_area,
+            &LayoutOptions::default(),
+            0,
+        );
+
+        assert_eq!(get_rects(&plan), vec![Rect::new(100, 30, 1000, 600)]);
+    }
+
+    #[test]
+    fn master_stack_stacks_the_other_windows_on_the_right() {
+        let options = LayoutOptions {
+            layout: Layout::MasterStack,
+            ..LayoutOptions::default()
+        };
+        let plan = compute_layout(
+            &create_tree(&[1, 2, 3]),
+            &Rect::new(0, 0, 1000, 601),
+            &options,
+            0,
+        );
+
+        assert_eq!(
+            get_rects(&plan),
+            vec![
+                Rect::new(0, 0, 550, 601),
+                Rect::new(550, 0, 1000, 301),
+                Rect::new(550, 301, 1000, 601),
+            ]
+        );
+    }
+
+    #[test]
+    fn grid_shares_the_last_row_between_the_remaining_windows() {
+        let options = LayoutOptions {
+            layout: Layout::Grid,
+            ..LayoutOptions::default()
+        };
+        let plan = compute_layout(
+            &create_tree(&[1, 2, 3, 4, 5]),
+            &Rect::new(0, 0, 1000, 600),
+            &options,
+            0,
+        );
+
+        assert_eq!(
+            get_rects(&plan),
+            vec![
+                Rect::new(0, 0, 333, 300),
+                Rect::new(333, 0, 667, 300),
+                Rect::new(667, 0, 1000, 300),
+                Rect::new(0, 300, 500, 600),
+                Rect::new(500, 300, 1000, 600),
+            ]
+        );
+    }
+
+    #[test]
+    fn scrolling_parks_the_columns_partially_off_the_monitor() {
+        let options = LayoutOptions {
+            layout: Layout::Scrolling,
+            ..LayoutOptions::default()
+        };
+        let plan = compute_layout(
+            &create_tree(&[1, 2, 3]),
+            &Rect::new(0, 0, 1000, 600),
+            &options,
+            250,
+        );
+
+        let parked_rect = get_parked_rect(&Rect::new(0, 0, 500, 600));
+        assert_eq!(
+            get_rects(&plan),
+            vec![parked_rect, Rect::new(250, 0, 750, 600), parked_rect]
+        );
+    }
+
+    #[test]
+    fn weights_split_the_width_proportionally() {
+        let mut tree = create_tree(&[1, 2]);
+        tree.childrens[0].weight = 3.0;
+        let plan = compute_layout(
+            &tree,
+            &Rect::new(0, 0, 1000, 600),
+            &LayoutOptions::default(),
+            0,
+        );
+
+        assert_eq!(
+            get_rects(&plan),
+            vec![Rect::new(0, 0, 750, 600), Rect::new(750, 0, 1000, 600)]
+        );
+    }
+
+    #[test]
+    fn tabbed_container_parks_the_inactive_childrens() {
+        let mut tree = create_tree(&[1, 2, 3]);
+        tree.container_mode = ContainerMode::Tabbed;
+        tree.active_children = 1;
+        let monitor_rect = Rect::new(0, 0, 1000, 600);
+        let plan = compute_layout(&tree, &monitor_rect, &LayoutOptions::default(), 0);
+
+        let parked_rect = get_parked_rect(&monitor_rect);
+        assert_eq!(
+            get_rects(&plan),
+            vec![parked_rect, monitor_rect, parked_rect]
+        );
+    }
+
+    #[test]
+    fn gaps_leave_the_parked_windows_at_the_parking_position() {
+        let mut tree = create_tree(&[1, 2]);
+        tree.container_mode = ContainerMode::Tabbed;
+        let options = LayoutOptions {
+            inner_gap: 10,
+            ..LayoutOptions::default()
+        };
+        let plan = compute_layout(&tree, &Rect::new(0, 0, 1000, 600), &options, 0);
+
+        assert!(!is_parked_rect(&plan[0].1));
+        assert!(is_parked_rect(&plan[1].1));
+    }
+
+    #[test]
+    fn applier_skips_the_windows_already_in_place() {
+        let system = FakeWindowSystem::new();
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1000, 600));
+        let first = system.open_window("first", monitor);
+        let second = system.open_window("second", monitor);
+        system.take_events();
+
+        let mut applier = LayoutApplier::new();
+        let mut plan = vec![
+            (first, Rect::new(0, 0, 500, 600)),
+            (second, Rect::new(500, 0, 1000, 600)),
+        ];
+        applier.apply(&system, &plan);
+        assert_eq!(system.take_events().len(), 2);
+
+        applier.apply(&system, &plan);
+        assert!(system.take_events().is_empty());
+
+        plan[1].1 = Rect::new(600, 0, 1000, 600);
+        applier.apply(&system, &plan);
+        assert_eq!(
+            system.take_events(),
+            vec![WindowEvent::MovedOrResized(second)]
+        );
+    }
+}
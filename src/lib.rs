@@ -0,0 +1,13 @@
+pub mod actions;
+pub mod config;
+pub mod events;
+pub mod layout;
+pub mod monitor;
+pub mod platform;
+pub mod rules;
+pub mod scratchpad;
+pub mod snapshot;
+pub mod tree;
+pub mod windows;
+pub mod wm;
+pub mod workspace;
@@ -1,39 +1,33 @@
-use actions::WmAction;
 use dotenv::dotenv;
-use input::create_inputs_window;
 use notify::{Error, Event, RecommendedWatcher, Watcher};
-use std::{
-    mem::zeroed,
-    sync::{
-        mpsc::{Receiver, Sender},
-        Arc, Mutex,
-    },
-    thread::JoinHandle,
+use std::sync::{
+    mpsc::{Receiver, RecvTimeoutError, Sender},
+    Arc, Mutex,
 };
+use std::time::Duration;
 
-use config::{Config, ConfigBuilder};
-use windows_sys::Win32::{
-    Foundation::POINT,
-    UI::WindowsAndMessaging::{
-        DispatchMessageW, GetCursorPos, GetMessageA, PeekMessageA, PostMessageA, TranslateMessage,
-        PM_REMOVE, WM_CLOSE, WM_HOTKEY,
-    },
-};
-use wm::WindowManager;
-
-use crate::input::{close_inputs_window, register_hotkeys};
+use wwm::config::{Config, ConfigBuilder};
+use wwm::events::WindowEvent;
 
-mod actions;
-mod config;
+#[cfg(windows)]
 mod input;
-mod monitor;
-mod tree;
-mod windows;
-mod wm;
-mod workspace;
 
+/// Time without any message after which the windows are fetched again, in case events were missed
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Everything the main loop waits for
+pub enum Message {
+    #[cfg(windows)]
+    Action(wwm::actions::WmAction),
+    Window(WindowEvent),
+    ConfigReloaded,
+    /// The process is interrupted, the windows are given back before it ends
+    Quit,
+}
+
+#[cfg(windows)]
 pub struct GlobalWindowData {
-    sender: Sender<WmAction>,
+    sender: Sender<Message>,
 }
 
 fn main() {
@@ -46,7 +40,15 @@ fn main() {
     let config = Arc::new(Mutex::new(get_configuration(config_path)));
     let cloned_config = Arc::clone(&config);
 
-    let (config_reload_sender, config_reload_receiver) = std::sync::mpsc::channel::<bool>();
+    let (sender, receiver) = std::sync::mpsc::channel::<Message>();
+    let config_reload_sender = sender.clone();
+    let quit_sender = sender.clone();
+
+    ctrlc::set_handler(move || {
+        // The main loop is gone when it stops receiving
+        let _ = quit_sender.send(Message::Quit);
+    })
+    .expect("Could not set the interruption handler !");
 
     let mut watcher = RecommendedWatcher::new(
         move |result: Result<Event, Error>| {
@@ -59,7 +61,7 @@ fn main() {
                     *cloned_config.lock().unwrap() = get_configuration(config_path);
 
                     config_reload_sender
-                        .send(true)
+                        .send(Message::ConfigReloaded)
                         .expect("Could not send the config updated event");
                 }
             }
@@ -72,46 +74,140 @@ fn main() {
         .watch(&get_config_path(), notify::RecursiveMode::NonRecursive)
         .expect("Could not start the config file hor reloading !");
 
-    let (hotkeys_sender, hotkeys_receiver) = std::sync::mpsc::channel();
-    let (shutdown_sender, shutdown_receiver) = std::sync::mpsc::channel::<bool>();
+    run(config, sender, receiver);
+}
+
+pub fn get_config_path() -> std::path::PathBuf {
+    let config_path_str = std::env::var("CONFIG_PATH").expect("Could not load the config file !");
+    let config_path = std::path::Path::new(config_path_str.as_str());
+    config_path.join("config").to_path_buf()
+}
+
+fn get_configuration(config_path: &str) -> Config {
+    ConfigBuilder::new(config_path).build()
+}
 
-    let mut window_manager = WindowManager::new(config);
+#[cfg(not(any(windows, target_os = "linux")))]
+fn run(_config: Arc<Mutex<Config>>, _sender: Sender<Message>, _receiver: Receiver<Message>) {
+    eprintln!("No window system backend is available on this platform");
+}
+
+/// Forward the events of the source to the main loop from a thread of their own
+fn spawn_window_events<E: wwm::events::WindowEventSource + Send + 'static>(
+    source: E,
+    sender: Sender<Message>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        source.listen(Box::new(move |event| {
+            // The main loop is gone when it stops receiving
+            let _ = sender.send(Message::Window(event));
+        }))
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn run(config: Arc<Mutex<Config>>, sender: Sender<Message>, receiver: Receiver<Message>) {
+    use wwm::{
+        platform::{x11::X11WindowSystem, WindowSystem},
+        wm::WindowManager,
+    };
+
+    let system = Arc::new(X11WindowSystem::new());
+    let mut window_manager = WindowManager::new(config, Arc::clone(&system));
     window_manager.get_monitors();
     window_manager.list_managable_windows();
 
-    let inputs_thread_handle = init_inputs_thread(hotkeys_sender, shutdown_receiver);
+    // The events are read from a connection of their own
+    spawn_window_events(X11WindowSystem::new(), sender);
 
     window_manager.fetch_windows();
     window_manager.arrange_workspaces();
 
-    let mut cursor_position: POINT = POINT { x: 0, y: 0 };
-
     loop {
-        if config_reload_receiver.try_recv().is_ok() {
-            window_manager.get_monitors();
-            println!("Config reloaded !");
-        }
+        let message = match receiver.recv_timeout(RECONCILE_INTERVAL) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
 
-        unsafe { GetCursorPos(&mut cursor_position) };
-        match hotkeys_receiver.try_recv() {
-            Ok(action) => match action {
-                WmAction::Workspace(action) => window_manager.handle_action(action),
-                WmAction::Close { hwnd } => {
-                    close_inputs_window(hwnd);
-                    shutdown_sender
-                        .send(true)
-                        .expect("Could not send the shutdown message");
-                    break;
-                }
-            },
-            Err(_) => {}
+        match message {
+            None => window_manager.fetch_windows(),
+            Some(Message::Window(event)) => window_manager.handle_window_event(event),
+            Some(Message::ConfigReloaded) => {
+                window_manager.get_monitors();
+                window_manager.fetch_windows();
+                println!("Config reloaded !");
+            }
+            Some(Message::Quit) => {
+                window_manager.restore_all();
+                break;
+            }
         }
 
-        window_manager.update_current_monitor(cursor_position.x, cursor_position.y);
-        window_manager.fetch_windows();
+        let (cursor_x, cursor_y) = system.get_cursor_position();
+        window_manager.update_current_monitor(cursor_x, cursor_y);
         window_manager.arrange_workspaces();
+    }
+}
+
+#[cfg(windows)]
+fn run(config: Arc<Mutex<Config>>, sender: Sender<Message>, receiver: Receiver<Message>) {
+    use wwm::{
+        actions::WmAction,
+        platform::{
+            win32::{Win32EventSource, Win32WindowSystem},
+            WindowSystem,
+        },
+        wm::WindowManager,
+    };
+
+    use crate::input::close_inputs_window;
 
-        std::thread::sleep(std::time::Duration::from_millis(500));
+    let system = Arc::new(Win32WindowSystem);
+    let mut window_manager = WindowManager::new(config, Arc::clone(&system));
+    window_manager.get_monitors();
+    window_manager.list_managable_windows();
+
+    let (inputs_thread_handle, hotkeys_thread_id) = init_inputs_thread(sender.clone());
+    spawn_window_events(Win32EventSource, sender);
+
+    window_manager.fetch_windows();
+    window_manager.arrange_workspaces();
+
+    loop {
+        let message = match receiver.recv_timeout(RECONCILE_INTERVAL) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        match message {
+            None => window_manager.fetch_windows(),
+            Some(Message::Action(WmAction::Workspace(action))) => {
+                let (cursor_x, cursor_y) = system.get_cursor_position();
+                window_manager.update_current_monitor(cursor_x, cursor_y);
+                window_manager.handle_action(action);
+            }
+            Some(Message::Action(WmAction::Close { hwnd })) => {
+                window_manager.restore_all();
+                close_inputs_window(hwnd);
+                stop_hotkeys_thread(hotkeys_thread_id);
+                break;
+            }
+            Some(Message::Window(event)) => window_manager.handle_window_event(event),
+            Some(Message::ConfigReloaded) => {
+                window_manager.get_monitors();
+                window_manager.fetch_windows();
+                println!("Config reloaded !");
+            }
+            Some(Message::Quit) => {
+                // The inputs threads end with the process
+                window_manager.restore_all();
+                return;
+            }
+        }
+
+        window_manager.arrange_workspaces();
     }
 
     for handle in inputs_thread_handle.into_iter() {
@@ -119,52 +215,50 @@ fn main() {
     }
 }
 
-pub fn get_config_path() -> std::path::PathBuf {
-    let config_path_str = std::env::var("CONFIG_PATH").expect("Could not load the config file !");
-    let config_path = std::path::Path::new(config_path_str.as_str());
-    config_path.join("config").to_path_buf()
-}
+/// Stop the thread forwarding the hotkeys, waking it up from its wait for messages
+#[cfg(windows)]
+fn stop_hotkeys_thread(thread_id: u32) {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{PostThreadMessageA, WM_QUIT};
 
-fn get_configuration(config_path: &str) -> Config {
-    ConfigBuilder::new(config_path).build()
+    unsafe { PostThreadMessageA(thread_id, WM_QUIT, 0, 0) };
 }
 
-fn init_inputs_thread(
-    hotkeys_sender: Sender<WmAction>,
-    shutdown_receiver: Receiver<bool>,
-) -> [JoinHandle<()>; 2] {
+/// Start the threads receiving the hotkeys, returning them with the id of the one to stop on close
+#[cfg(windows)]
+fn init_inputs_thread(hotkeys_sender: Sender<Message>) -> ([std::thread::JoinHandle<()>; 2], u32) {
+    use std::mem::zeroed;
+    use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, GetMessageA, PeekMessageA, PostMessageA, TranslateMessage, PM_NOREMOVE,
+        WM_CLOSE, WM_HOTKEY, WM_USER,
+    };
+
+    use crate::input::{create_inputs_window, register_hotkeys};
+
     let (hwnd_sender, hwnd_receiver) = std::sync::mpsc::channel::<isize>();
+    let (thread_id_sender, thread_id_receiver) = std::sync::mpsc::channel::<u32>();
 
     let desktop_handle = std::thread::spawn(move || {
         register_hotkeys();
         let mut msg = unsafe { zeroed() };
 
-        let global_window_hwnd;
-        loop {
-            match hwnd_receiver.try_recv() {
-                Ok(hwnd) => {
-                    global_window_hwnd = hwnd;
-                    break;
-                }
-                Err(_) => {}
-            }
-        }
+        // Peeking creates the message queue of the thread, so the quit message can be posted to it
+        unsafe { PeekMessageA(&mut msg, 0, WM_USER, WM_USER, PM_NOREMOVE) };
+        thread_id_sender
+            .send(unsafe { GetCurrentThreadId() })
+            .expect("Could not send the thread id to the main thread !");
 
-        loop {
-            match shutdown_receiver.try_recv() {
-                Ok(_) => {
-                    println!("Receive shutdown");
-                    break;
-                }
-                Err(_) => {}
-            }
+        let global_window_hwnd = hwnd_receiver
+            .recv()
+            .expect("Could not receive the hwnd of the inputs window !");
 
-            if unsafe { PeekMessageA(&mut msg, 0, 0, 0, PM_REMOVE) } > 0 {
-                if msg.message == WM_HOTKEY {
-                    unsafe { PostMessageA(global_window_hwnd, msg.message, msg.wParam, 0) };
-                }
+        // The thread sleeps until a hotkey is pressed, the quit message ends the loop
+        while unsafe { GetMessageA(&mut msg, 0, 0, 0) } > 0 {
+            if msg.message == WM_HOTKEY {
+                unsafe { PostMessageA(global_window_hwnd, msg.message, msg.wParam, 0) };
             }
         }
+        println!("Receive shutdown");
     });
 
     let window_handle = std::thread::spawn(move || {
@@ -187,5 +281,9 @@ fn init_inputs_thread(
         }
     });
 
-    [desktop_handle, window_handle]
+    let hotkeys_thread_id = thread_id_receiver
+        .recv()
+        .expect("Could not receive the id of the hotkeys thread !");
+
+    ([desktop_handle, window_handle], hotkeys_thread_id)
 }
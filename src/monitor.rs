@@ -1,115 +1,201 @@
-use windows_sys::Win32::Foundation::{LPARAM, RECT};
-use windows_sys::Win32::Graphics::Gdi::{
-    EnumDisplayDevicesW, EnumDisplayMonitors, GetMonitorInfoW, MonitorFromWindow, DISPLAY_DEVICEW,
-    HDC, HMONITOR, MONITORINFO, MONITOR_DEFAULTTONEAREST,
-};
-
-#[derive(Clone)]
-pub struct Monitor {
-    pub name: String,
-    pub monitor_handle: HMONITOR,
-    pub rect: RECT,
-    pub width: i32,
-    pub height: i32,
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
 }
-impl Monitor {
-    pub fn is_point_in_monitor(&self, x: i32, y: i32) -> bool {
-        x > self.rect.left && x < self.rect.right && y > self.rect.top && y < self.rect.bottom
+impl Rect {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
     }
-}
 
-pub struct Monitors;
-impl Monitors {
-    pub fn get_monitors_list() -> Vec<Monitor> {
-        let mut monitors = Vec::new();
-        let monitor_handles = Monitors::get_monitors_handles();
-
-        let mut display_device: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
-        display_device.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
-        let mut i: usize = 0;
-        while unsafe { EnumDisplayDevicesW(std::ptr::null(), i as u32, &mut display_device, 0) }
-            != 0
-        {
-            let mut monitor: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
-            monitor.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
-
-            let mut j: usize = 0;
-            while unsafe {
-                EnumDisplayDevicesW(
-                    display_device.DeviceName.as_ptr(),
-                    j as u32,
-                    &mut monitor,
-                    0,
-                )
-            } != 0
-            {
-                let device_name = Monitors::to_string(&monitor.DeviceString);
-
-                monitors.push(Monitors::create_monitor(device_name, monitor_handles[i]));
-                j += 1;
-            }
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
 
-            display_device = unsafe { std::mem::zeroed() };
-            display_device.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
 
-            i += 1;
-        }
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x > self.left && x < self.right && y > self.top && y < self.bottom
+    }
 
-        monitors
+    /// Shrink the rect by the given amount on each edge
+    pub fn inset(&self, left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self::new(
+            self.left + left,
+            self.top + top,
+            self.right - right,
+            self.bottom - bottom,
+        )
     }
 
-    fn get_monitors_handles() -> Vec<HMONITOR> {
-        let mut monitors: Vec<HMONITOR> = Vec::new();
+    pub fn intersect(&self, other: &Rect) -> Self {
+        Self::new(
+            self.left.max(other.left),
+            self.top.max(other.top),
+            self.right.min(other.right),
+            self.bottom.min(other.bottom),
+        )
+    }
 
-        unsafe {
-            EnumDisplayMonitors(
-                0,
-                std::ptr::null_mut(),
-                Some(enum_monitors_callback),
-                &mut monitors as *mut Vec<HMONITOR> as LPARAM,
-            );
-        }
+    pub fn center(&self) -> (i32, i32) {
+        (self.left + self.width() / 2, self.top + self.height() / 2)
+    }
 
-        monitors
+    /// Manhattan distance between the point and the nearest edge of the rect, 0 when it is inside
+    pub fn distance_to(&self, x: i32, y: i32) -> i32 {
+        let dx = (x - x.clamp(self.left, self.right)).abs();
+        let dy = (y - y.clamp(self.top, self.bottom)).abs();
+        dx + dy
     }
+}
 
-    fn create_monitor(device_name: String, monitor: HMONITOR) -> Monitor {
-        let mut monitor_info: MONITORINFO = unsafe { std::mem::zeroed() };
-        monitor_info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+impl Edge {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "top" => Some(Edge::Top),
+            "bottom" => Some(Edge::Bottom),
+            "left" => Some(Edge::Left),
+            "right" => Some(Edge::Right),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Space reserved on the edges of a monitor, for example for a status bar
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Struts {
+    pub top: i32,
+    pub bottom: i32,
+    pub left: i32,
+    pub right: i32,
+}
+impl Struts {
+    pub fn set(&mut self, edge: Edge, size: i32) {
+        match edge {
+            Edge::Top => self.top = size,
+            Edge::Bottom => self.bottom = size,
+            Edge::Left => self.left = size,
+            Edge::Right => self.right = size,
+        }
+    }
+}
 
-        unsafe {
-            GetMonitorInfoW(monitor, &mut monitor_info);
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    pub name: String,
+    pub monitor_handle: isize,
+    pub rect: Rect,
+    /// Area available for the windows, without the taskbars and the reserved struts
+    pub work_area: Rect,
+    pub width: i32,
+    pub height: i32,
+}
+impl Monitor {
+    pub fn new(name: &str, monitor_handle: isize, rect: Rect) -> Self {
+        Self {
+            name: name.to_owned(),
+            monitor_handle,
+            rect,
+            work_area: rect,
+            width: rect.width(),
+            height: rect.height(),
         }
+    }
 
-        let screen_width = monitor_info.rcMonitor.right - monitor_info.rcMonitor.left;
-        let screen_height = monitor_info.rcMonitor.bottom - monitor_info.rcMonitor.top;
+    pub fn with_work_area(mut self, work_area: Rect) -> Self {
+        self.work_area = work_area;
+        self
+    }
 
-        return Monitor {
-            name: device_name,
-            monitor_handle: monitor,
-            rect: monitor_info.rcMonitor,
-            width: screen_width,
-            height: screen_height,
-        };
+    pub fn reserve_struts(&mut self, struts: &Struts) {
+        self.work_area = self
+            .work_area
+            .inset(struts.left, struts.top, struts.right, struts.bottom);
     }
 
-    fn to_string(ptr: &[u16]) -> String {
-        let len = ptr.iter().position(|&c| c == 0).unwrap_or(ptr.len());
-        String::from_utf16_lossy(&ptr[0..len])
+    pub fn is_point_in_monitor(&self, x: i32, y: i32) -> bool {
+        self.rect.contains(x, y)
     }
 }
 
-pub fn get_monitor_from_window(window_hwnd: isize) -> HMONITOR {
-    unsafe { MonitorFromWindow(window_hwnd, MONITOR_DEFAULTTONEAREST) }
+/// Get the handle of the monitor containing the center of the rect, the nearest one otherwise
+pub fn get_monitor_from_rect(monitors: &[Monitor], rect: &Rect) -> isize {
+    let (center_x, center_y) = rect.center();
+    monitors
+        .iter()
+        .min_by_key(|monitor| monitor.rect.distance_to(center_x, center_y))
+        .map(|monitor| monitor.monitor_handle)
+        .unwrap_or(0)
 }
 
-unsafe extern "system" fn enum_monitors_callback(
-    monitor: HMONITOR,
-    _: HDC,
-    _: *mut RECT,
-    data: LPARAM,
-) -> i32 {
-    let monitors = &mut *(data as *mut Vec<HMONITOR>);
-    monitors.push(monitor);
-    1
+/// Get the key of the rect nearest to the given one in the direction, preferring the rects facing it
+pub fn get_nearest_in_direction(
+    rects: &[(isize, Rect)],
+    rect: &Rect,
+    direction: &Direction,
+) -> Option<isize> {
+    rects
+        .iter()
+        .filter_map(|(key, candidate)| {
+            // Distance along the direction, and the ranges compared on the other axis
+            let (distance, range, candidate_range) = match direction {
+                Direction::Left => (
+                    rect.left - candidate.right,
+                    (rect.top, rect.bottom),
+                    (candidate.top, candidate.bottom),
+                ),
+                Direction::Right => (
+                    candidate.left - rect.right,
+                    (rect.top, rect.bottom),
+                    (candidate.top, candidate.bottom),
+                ),
+                Direction::Up => (
+                    rect.top - candidate.bottom,
+                    (rect.left, rect.right),
+                    (candidate.left, candidate.right),
+                ),
+                Direction::Down => (
+                    candidate.top - rect.bottom,
+                    (rect.left, rect.right),
+                    (candidate.left, candidate.right),
+                ),
+            };
+            if distance < 0 {
+                return None;
+            }
+
+            let gap = (candidate_range.0 - range.1)
+                .max(range.0 - candidate_range.1)
+                .max(0);
+            let center_offset =
+                ((candidate_range.0 + candidate_range.1) - (range.0 + range.1)).abs();
+
+            Some(((gap, distance, center_offset), *key))
+        })
+        .min_by_key(|(score, _)| *score)
+        .map(|(_, key)| key)
 }
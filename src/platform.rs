@@ -0,0 +1,48 @@
+use crate::monitor::{Monitor, Rect};
+
+pub mod fake;
+#[cfg(windows)]
+pub mod win32;
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+/// Operations the window manager needs from the underlying window system
+pub trait WindowSystem {
+    /// List the handles of every visible top-level window
+    fn get_windows(&self) -> Vec<isize>;
+
+    /// Whether the window is one `get_windows` lists, without enumerating all of them
+    fn is_manageable(&self, hwnd: isize) -> bool;
+
+    fn get_window_title(&self, hwnd: isize) -> String;
+
+    fn get_window_class(&self, hwnd: isize) -> Option<String>;
+
+    /// Get the id of the process owning the window
+    fn get_window_process_id(&self, hwnd: isize) -> Option<u32>;
+
+    /// Get the path of the executable run by the process
+    fn get_process_executable(&self, process_id: u32) -> Option<String>;
+
+    fn get_window_rect(&self, hwnd: isize) -> Option<Rect>;
+
+    fn set_window_pos(&self, hwnd: isize, x: i32, y: i32, width: i32, height: i32) -> bool;
+
+    /// Bring the window above every other window
+    fn put_on_top(&self, hwnd: isize);
+
+    fn get_monitors(&self) -> Vec<Monitor>;
+
+    /// Get the handle of the monitor displaying the biggest part of the window
+    fn get_monitor_from_window(&self, hwnd: isize) -> isize;
+
+    fn get_cursor_position(&self) -> (i32, i32);
+
+    /// Get the window receiving the keyboard input
+    fn get_foreground_window(&self) -> Option<isize>;
+
+    fn set_foreground_window(&self, hwnd: isize);
+
+    /// Whether the window is a dialog or a tool window owned by another window
+    fn is_dialog(&self, hwnd: isize) -> bool;
+}
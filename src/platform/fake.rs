@@ -0,0 +1,292 @@
+use std::sync::Mutex;
+
+use crate::events::{WindowEvent, WindowEventCallback, WindowEventSource};
+use crate::monitor::{get_monitor_from_rect, Monitor, Rect};
+
+use super::WindowSystem;
+
+#[derive(Debug, Clone)]
+pub struct FakeWindow {
+    pub hwnd: isize,
+    pub title: String,
+    pub rect: Rect,
+    pub visible: bool,
+    pub dialog: bool,
+    pub class: Option<String>,
+    pub process_id: Option<u32>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    monitors: Vec<Monitor>,
+    // Ordered from the bottom to the top of the z-order
+    windows: Vec<FakeWindow>,
+    next_handle: isize,
+    executables: Vec<(u32, String)>,
+    /// Events not delivered yet, in the order they happened
+    events: Vec<WindowEvent>,
+    cursor_position: (i32, i32),
+    foreground_window: Option<isize>,
+}
+
+/// In-memory window system that can be scripted to test the window manager without a desktop
+#[derive(Default)]
+pub struct FakeWindowSystem {
+    state: Mutex<FakeState>,
+}
+impl FakeWindowSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_monitor(&self, name: &str, rect: Rect) -> isize {
+        let mut state = self.state.lock().unwrap();
+        state.next_handle += 1;
+
+        let handle = state.next_handle;
+        state.monitors.push(Monitor::new(name, handle, rect));
+
+        handle
+    }
+
+    /// Reserve a part of the monitor, like a taskbar would do
+    pub fn set_work_area(&self, monitor_handle: isize, work_area: Rect) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(monitor) = state
+            .monitors
+            .iter_mut()
+            .find(|monitor| monitor.monitor_handle == monitor_handle)
+        {
+            monitor.work_area = work_area;
+        }
+    }
+
+    /// Open a visible window filling the given monitor
+    pub fn open_window(&self, title: &str, monitor_handle: isize) -> isize {
+        let mut state = self.state.lock().unwrap();
+        state.next_handle += 1;
+
+        let hwnd = state.next_handle;
+        let rect = state
+            .monitors
+            .iter()
+            .find(|monitor| monitor.monitor_handle == monitor_handle)
+            .map(|monitor| monitor.rect)
+            .unwrap_or_default();
+        state.windows.push(FakeWindow {
+            hwnd,
+            title: title.to_owned(),
+            rect,
+            visible: true,
+            dialog: false,
+            class: None,
+            process_id: None,
+        });
+        state.events.push(WindowEvent::Created(hwnd));
+
+        hwnd
+    }
+
+    pub fn close_window(&self, hwnd: isize) {
+        let mut state = self.state.lock().unwrap();
+        state.windows.retain(|window| window.hwnd != hwnd);
+        if state.foreground_window == Some(hwnd) {
+            state.foreground_window = None;
+        }
+        state.events.push(WindowEvent::Destroyed(hwnd));
+    }
+
+    pub fn set_title(&self, hwnd: isize, title: &str) {
+        if self.update_window(hwnd, |window| window.title = title.to_owned()) {
+            self.push_event(WindowEvent::TitleChanged(hwnd));
+        }
+    }
+
+    /// Hide the window like a minimisation would, or restore it
+    pub fn set_visible(&self, hwnd: isize, visible: bool) {
+        if self.update_window(hwnd, |window| window.visible = visible) {
+            self.push_event(if visible {
+                WindowEvent::Created(hwnd)
+            } else {
+                WindowEvent::Minimized(hwnd)
+            });
+        }
+    }
+
+    pub fn set_dialog(&self, hwnd: isize, dialog: bool) {
+        self.update_window(hwnd, |window| window.dialog = dialog);
+    }
+
+    pub fn set_class(&self, hwnd: isize, class: &str) {
+        self.update_window(hwnd, |window| window.class = Some(class.to_owned()));
+    }
+
+    /// Make the window owned by a process running the executable
+    pub fn set_process(&self, hwnd: isize, process_id: u32, executable: &str) {
+        self.update_window(hwnd, |window| window.process_id = Some(process_id));
+
+        let mut state = self.state.lock().unwrap();
+        state
+            .executables
+            .retain(|(existing_id, _)| *existing_id != process_id);
+        state.executables.push((process_id, executable.to_owned()));
+    }
+
+    pub fn set_cursor_position(&self, x: i32, y: i32) {
+        self.state.lock().unwrap().cursor_position = (x, y);
+    }
+
+    pub fn get_window(&self, hwnd: isize) -> Option<FakeWindow> {
+        self.state
+            .lock()
+            .unwrap()
+            .windows
+            .iter()
+            .find(|window| window.hwnd == hwnd)
+            .cloned()
+    }
+
+    /// Get the handle of the window at the top of the z-order
+    pub fn get_top_window(&self) -> Option<isize> {
+        self.state
+            .lock()
+            .unwrap()
+            .windows
+            .last()
+            .map(|window| window.hwnd)
+    }
+
+    /// Remove the events not delivered yet
+    pub fn take_events(&self) -> Vec<WindowEvent> {
+        std::mem::take(&mut self.state.lock().unwrap().events)
+    }
+
+    fn push_event(&self, event: WindowEvent) {
+        self.state.lock().unwrap().events.push(event);
+    }
+
+    fn update_window<F: FnOnce(&mut FakeWindow)>(&self, hwnd: isize, update: F) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.windows.iter_mut().find(|window| window.hwnd == hwnd) {
+            Some(window) => {
+                update(window);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl WindowSystem for FakeWindowSystem {
+    fn get_windows(&self) -> Vec<isize> {
+        self.state
+            .lock()
+            .unwrap()
+            .windows
+            .iter()
+            .filter(|window| window.visible)
+            .map(|window| window.hwnd)
+            .collect()
+    }
+
+    fn is_manageable(&self, hwnd: isize) -> bool {
+        self.get_window(hwnd)
+            .map(|window| window.visible)
+            .unwrap_or(false)
+    }
+
+    fn get_window_title(&self, hwnd: isize) -> String {
+        self.get_window(hwnd)
+            .map(|window| window.title)
+            .unwrap_or_default()
+    }
+
+    fn get_window_class(&self, hwnd: isize) -> Option<String> {
+        self.get_window(hwnd)?.class
+    }
+
+    fn get_window_process_id(&self, hwnd: isize) -> Option<u32> {
+        self.get_window(hwnd)?.process_id
+    }
+
+    fn get_process_executable(&self, process_id: u32) -> Option<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .executables
+            .iter()
+            .find(|(existing_id, _)| *existing_id == process_id)
+            .map(|(_, executable)| executable.clone())
+    }
+
+    fn get_window_rect(&self, hwnd: isize) -> Option<Rect> {
+        self.get_window(hwnd).map(|window| window.rect)
+    }
+
+    fn set_window_pos(&self, hwnd: isize, x: i32, y: i32, width: i32, height: i32) -> bool {
+        let moved = self.update_window(hwnd, |window| {
+            window.rect = Rect::new(x, y, x + width, y + height)
+        });
+        if moved {
+            self.push_event(WindowEvent::MovedOrResized(hwnd));
+        }
+
+        moved
+    }
+
+    fn put_on_top(&self, hwnd: isize) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(index) = state.windows.iter().position(|window| window.hwnd == hwnd) {
+            let window = state.windows.remove(index);
+            state.windows.push(window);
+        }
+    }
+
+    fn get_monitors(&self) -> Vec<Monitor> {
+        self.state.lock().unwrap().monitors.clone()
+    }
+
+    fn get_monitor_from_window(&self, hwnd: isize) -> isize {
+        let state = self.state.lock().unwrap();
+        let Some(window) = state.windows.iter().find(|window| window.hwnd == hwnd) else {
+            return 0;
+        };
+
+        get_monitor_from_rect(&state.monitors, &window.rect)
+    }
+
+    fn get_cursor_position(&self) -> (i32, i32) {
+        self.state.lock().unwrap().cursor_position
+    }
+
+    fn get_foreground_window(&self) -> Option<isize> {
+        self.state.lock().unwrap().foreground_window
+    }
+
+    /// Bring the window to the foreground like a click on it would
+    fn set_foreground_window(&self, hwnd: isize) {
+        if self.get_window(hwnd).is_none() {
+            return;
+        }
+
+        self.put_on_top(hwnd);
+        let mut state = self.state.lock().unwrap();
+        state.foreground_window = Some(hwnd);
+        state.events.push(WindowEvent::FocusChanged(hwnd));
+    }
+
+    fn is_dialog(&self, hwnd: isize) -> bool {
+        self.get_window(hwnd)
+            .map(|window| window.dialog)
+            .unwrap_or(false)
+    }
+}
+
+/// Deliver the pending events then return instead of blocking
+impl WindowEventSource for FakeWindowSystem {
+    fn listen(&self, mut callback: WindowEventCallback) {
+        for event in self.take_events() {
+            callback(event);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::cell::RefCell;
+
+use windows_sys::Win32::Foundation::{CloseHandle, LPARAM, POINT, RECT};
+use windows_sys::Win32::Graphics::Gdi::{
+    EnumDisplayDevicesW, EnumDisplayMonitors, GetMonitorInfoW, MonitorFromWindow, DISPLAY_DEVICEW,
+    HDC, HMONITOR, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+};
+use windows_sys::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows_sys::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    BringWindowToTop, DispatchMessageW, EnumWindows, GetAncestor, GetClassNameW, GetCursorPos,
+    GetForegroundWindow, GetMessageW, GetWindow, GetWindowLongPtrW, GetWindowRect, GetWindowTextW,
+    GetWindowThreadProcessId, IsIconic, IsWindowVisible, SetForegroundWindow, SetWindowPos,
+    TranslateMessage, CHILDID_SELF, EVENT_OBJECT_DESTROY, EVENT_OBJECT_HIDE,
+    EVENT_OBJECT_NAMECHANGE, EVENT_OBJECT_SHOW, EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_MINIMIZEEND,
+    EVENT_SYSTEM_MINIMIZESTART, EVENT_SYSTEM_MOVESIZEEND, GA_ROOT, GWL_EXSTYLE, GW_OWNER,
+    OBJID_WINDOW, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS, WS_EX_TOOLWINDOW,
+};
+
+use crate::events::{WindowEvent, WindowEventCallback, WindowEventSource};
+use crate::monitor::{Monitor, Rect};
+
+use super::WindowSystem;
+
+pub struct Win32WindowSystem;
+impl WindowSystem for Win32WindowSystem {
+    fn get_windows(&self) -> Vec<isize> {
+        let mut windows: Vec<isize> = Vec::new();
+
+        unsafe {
+            EnumWindows(Some(get_window_def), &mut windows as *mut _ as LPARAM);
+        }
+
+        windows
+    }
+
+    fn is_manageable(&self, hwnd: isize) -> bool {
+        unsafe { is_manageable_window(hwnd) }
+    }
+
+    fn get_window_title(&self, hwnd: isize) -> String {
+        let mut text: [u16; 512] = [0; 512];
+        let len = unsafe { GetWindowTextW(hwnd, text.as_mut_ptr(), text.len() as i32) };
+
+        String::from_utf16_lossy(&text[..len as usize])
+    }
+
+    fn get_window_class(&self, hwnd: isize) -> Option<String> {
+        let mut text: [u16; 256] = [0; 256];
+        let len = unsafe { GetClassNameW(hwnd, text.as_mut_ptr(), text.len() as i32) };
+        if len == 0 {
+            return None;
+        }
+
+        Some(String::from_utf16_lossy(&text[..len as usize]))
+    }
+
+    fn get_window_process_id(&self, hwnd: isize) -> Option<u32> {
+        let mut process_id = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, &mut process_id) };
+
+        Some(process_id).filter(|process_id| *process_id != 0)
+    }
+
+    fn get_process_executable(&self, process_id: u32) -> Option<String> {
+        let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id) };
+        if process == 0 {
+            return None;
+        }
+
+        let mut text: [u16; 1024] = [0; 1024];
+        let mut len = text.len() as u32;
+        let succeeded = unsafe {
+            QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, text.as_mut_ptr(), &mut len)
+        };
+        unsafe { CloseHandle(process) };
+        if succeeded == 0 {
+            return None;
+        }
+
+        Some(String::from_utf16_lossy(&text[..len as usize]))
+    }
+
+    fn get_window_rect(&self, hwnd: isize) -> Option<Rect> {
+        let mut rect: RECT = unsafe { std::mem::zeroed() };
+        if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+            return None;
+        }
+
+        Some(Rect::new(rect.left, rect.top, rect.right, rect.bottom))
+    }
+
+    fn set_window_pos(&self, hwnd: isize, x: i32, y: i32, width: i32, height: i32) -> bool {
+        unsafe { SetWindowPos(hwnd, 0, x, y, width, height, 0x0040) == 1 }
+    }
+
+    fn put_on_top(&self, hwnd: isize) {
+        unsafe { BringWindowToTop(hwnd) };
+    }
+
+    fn get_monitors(&self) -> Vec<Monitor> {
+        Monitors::get_monitors_list()
+    }
+
+    fn get_monitor_from_window(&self, hwnd: isize) -> isize {
+        unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) }
+    }
+
+    fn get_cursor_position(&self) -> (i32, i32) {
+        let mut cursor_position = POINT { x: 0, y: 0 };
+        unsafe { GetCursorPos(&mut cursor_position) };
+
+        (cursor_position.x, cursor_position.y)
+    }
+
+    fn get_foreground_window(&self) -> Option<isize> {
+        Some(unsafe { GetForegroundWindow() }).filter(|hwnd| *hwnd != 0)
+    }
+
+    fn set_foreground_window(&self, hwnd: isize) {
+        unsafe { SetForegroundWindow(hwnd) };
+    }
+
+    fn is_dialog(&self, hwnd: isize) -> bool {
+        unsafe { GetWindow(hwnd, GW_OWNER) != 0 }
+    }
+}
+
+struct Monitors;
+impl Monitors {
+    fn get_monitors_list() -> Vec<Monitor> {
+        let mut monitors = Vec::new();
+        let monitor_handles = Monitors::get_monitors_handles();
+
+        let mut display_device: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
+        display_device.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
+        let mut i: usize = 0;
+        while unsafe { EnumDisplayDevicesW(std::ptr::null(), i as u32, &mut display_device, 0) }
+            != 0
+        {
+            let mut monitor: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
+            monitor.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
+
+            let mut j: usize = 0;
+            while unsafe {
+                EnumDisplayDevicesW(
+                    display_device.DeviceName.as_ptr(),
+                    j as u32,
+                    &mut monitor,
+                    0,
+                )
+            } != 0
+            {
+                let device_name = Monitors::to_string(&monitor.DeviceString);
+
+                monitors.push(Monitors::create_monitor(device_name, monitor_handles[i]));
+                j += 1;
+            }
+
+            display_device = unsafe { std::mem::zeroed() };
+            display_device.cb = std::mem::size_of::<DISPLAY_DEVICEW>() as u32;
+
+            i += 1;
+        }
+
+        monitors
+    }
+
+    fn get_monitors_handles() -> Vec<HMONITOR> {
+        let mut monitors: Vec<HMONITOR> = Vec::new();
+
+        unsafe {
+            EnumDisplayMonitors(
+                0,
+                std::ptr::null_mut(),
+                Some(enum_monitors_callback),
+                &mut monitors as *mut Vec<HMONITOR> as LPARAM,
+            );
+        }
+
+        monitors
+    }
+
+    fn create_monitor(device_name: String, monitor: HMONITOR) -> Monitor {
+        let mut monitor_info: MONITORINFO = unsafe { std::mem::zeroed() };
+        monitor_info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+
+        unsafe {
+            GetMonitorInfoW(monitor, &mut monitor_info);
+        }
+
+        let rect = monitor_info.rcMonitor;
+        let work_area = monitor_info.rcWork;
+        Monitor::new(
+            &device_name,
+            monitor,
+            Rect::new(rect.left, rect.top, rect.right, rect.bottom),
+        )
+        .with_work_area(Rect::new(
+            work_area.left,
+            work_area.top,
+            work_area.right,
+            work_area.bottom,
+        ))
+    }
+
+    fn to_string(ptr: &[u16]) -> String {
+        let len = ptr.iter().position(|&c| c == 0).unwrap_or(ptr.len());
+        String::from_utf16_lossy(&ptr[0..len])
+    }
+}
+
+thread_local! {
+    static EVENT_CALLBACK: RefCell<Option<WindowEventCallback>> = RefCell::new(None);
+}
+
+/// Window events fed by the WinEvent hooks
+pub struct Win32EventSource;
+impl WindowEventSource for Win32EventSource {
+    fn listen(&self, callback: WindowEventCallback) {
+        // The out of context hooks call back on this thread while it pumps its messages
+        EVENT_CALLBACK.with(|event_callback| *event_callback.borrow_mut() = Some(callback));
+
+        let event_ranges = [
+            (EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_FOREGROUND),
+            (EVENT_SYSTEM_MOVESIZEEND, EVENT_SYSTEM_MOVESIZEEND),
+            (EVENT_SYSTEM_MINIMIZESTART, EVENT_SYSTEM_MINIMIZEEND),
+            (EVENT_OBJECT_DESTROY, EVENT_OBJECT_HIDE),
+            (EVENT_OBJECT_NAMECHANGE, EVENT_OBJECT_NAMECHANGE),
+        ];
+        let hooks: Vec<HWINEVENTHOOK> = event_ranges
+            .iter()
+            .map(|(event_min, event_max)| unsafe {
+                SetWinEventHook(
+                    *event_min,
+                    *event_max,
+                    0,
+                    Some(win_event_proc),
+                    0,
+                    0,
+                    WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+                )
+            })
+            .collect();
+
+        let mut msg = unsafe { std::mem::zeroed() };
+        while unsafe { GetMessageW(&mut msg, 0, 0, 0) } > 0 {
+            unsafe { TranslateMessage(&msg) };
+            unsafe { DispatchMessageW(&msg) };
+        }
+
+        for hook in hooks {
+            unsafe { UnhookWinEvent(hook) };
+        }
+    }
+}
+
+unsafe extern "system" fn win_event_proc(
+    _hook: HWINEVENTHOOK,
+    event: u32,
+    hwnd: isize,
+    object_id: i32,
+    child_id: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    // Only keep the events about the windows themselves, not their scrollbars, carets...
+    if hwnd == 0 || object_id != OBJID_WINDOW || child_id != CHILDID_SELF as i32 {
+        return;
+    }
+
+    let event = match event {
+        EVENT_OBJECT_SHOW | EVENT_SYSTEM_MINIMIZEEND => WindowEvent::Created(hwnd),
+        EVENT_OBJECT_DESTROY | EVENT_OBJECT_HIDE => WindowEvent::Destroyed(hwnd),
+        EVENT_OBJECT_NAMECHANGE => WindowEvent::TitleChanged(hwnd),
+        EVENT_SYSTEM_MOVESIZEEND => WindowEvent::MovedOrResized(hwnd),
+        EVENT_SYSTEM_FOREGROUND => WindowEvent::FocusChanged(hwnd),
+        EVENT_SYSTEM_MINIMIZESTART => WindowEvent::Minimized(hwnd),
+        _ => return,
+    };
+
+    EVENT_CALLBACK.with(|event_callback| {
+        if let Some(callback) = event_callback.borrow_mut().as_mut() {
+            callback(event);
+        }
+    });
+}
+
+/// Whether the window is a visible top-level window, neither minimised nor a tool window
+unsafe fn is_manageable_window(hwnd: isize) -> bool {
+    IsWindowVisible(hwnd) != 0
+        && IsIconic(hwnd) == 0
+        && GetAncestor(hwnd, GA_ROOT) == hwnd
+        && GetWindowLongPtrW(hwnd, GWL_EXSTYLE) & WS_EX_TOOLWINDOW as isize == 0
+}
+
+unsafe extern "system" fn get_window_def(hwnd: isize, data: LPARAM) -> i32 {
+    if !is_manageable_window(hwnd) {
+        return 1;
+    }
+
+    let windows = &mut *(data as *mut Vec<isize>);
+    windows.push(hwnd);
+    1
+}
+
+unsafe extern "system" fn enum_monitors_callback(
+    monitor: HMONITOR,
+    _: HDC,
+    _: *mut RECT,
+    data: LPARAM,
+) -> i32 {
+    let monitors = &mut *(data as *mut Vec<HMONITOR>);
+    monitors.push(monitor);
+    1
+}
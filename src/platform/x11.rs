@@ -0,0 +1,563 @@
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xinerama::ConnectionExt as _;
+use x11rb::protocol::xproto::{
+    AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConfigureWindowAux,
+    ConnectionExt as _, EventMask, InputFocus, MapState, StackMode,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+use crate::events::{WindowEvent, WindowEventCallback, WindowEventSource};
+use crate::monitor::{get_monitor_from_rect, Monitor, Rect};
+
+use super::WindowSystem;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_ACTIVE_WINDOW,
+        _NET_CLIENT_LIST,
+        _NET_WORKAREA,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_WM_WINDOW_TYPE_DIALOG,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_UTILITY,
+        UTF8_STRING,
+    }
+}
+
+pub struct X11WindowSystem {
+    connection: RustConnection,
+    screen_index: usize,
+    root: u32,
+    atoms: Atoms,
+}
+impl X11WindowSystem {
+    /// Connect to the X server named by the DISPLAY environment variable
+    pub fn new() -> Self {
+        let (connection, screen_index) =
+            x11rb::connect(None).expect("Could not connect to the X server !");
+        let root = connection.setup().roots[screen_index].root;
+        let atoms = Atoms::new(&connection)
+            .expect("Could not intern the X11 atoms !")
+            .reply()
+            .expect("Could not intern the X11 atoms !");
+
+        Self {
+            connection,
+            screen_index,
+            root,
+            atoms,
+        }
+    }
+
+    /// Get the windows listed by the running window manager, if there is one
+    fn get_client_list(&self) -> Option<Vec<u32>> {
+        let reply = self
+            .connection
+            .get_property(
+                false,
+                self.root,
+                self.atoms._NET_CLIENT_LIST,
+                AtomEnum::WINDOW,
+                0,
+                u32::MAX,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+
+        let windows: Vec<u32> = reply.value32()?.collect();
+        if windows.is_empty() {
+            return None;
+        }
+
+        Some(windows)
+    }
+
+    fn get_active_window(&self) -> Option<u32> {
+        self.connection
+            .get_property(
+                false,
+                self.root,
+                self.atoms._NET_ACTIVE_WINDOW,
+                AtomEnum::WINDOW,
+                0,
+                1,
+            )
+            .ok()?
+            .reply()
+            .ok()?
+            .value32()?
+            .next()
+            .filter(|window| *window != 0)
+    }
+
+    /// Ask the X server to report the changes of the window
+    fn select_window_events(&self, window: u32, event_mask: EventMask) {
+        let attributes = ChangeWindowAttributesAux::new().event_mask(event_mask);
+        let _ = self
+            .connection
+            .change_window_attributes(window, &attributes);
+    }
+
+    fn get_root_childrens(&self) -> Vec<u32> {
+        self.connection
+            .query_tree(self.root)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.children)
+            .unwrap_or_default()
+    }
+
+    fn is_window_visible(&self, window: u32) -> bool {
+        self.connection
+            .get_window_attributes(window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.map_state == MapState::VIEWABLE && !reply.override_redirect)
+            .unwrap_or(false)
+    }
+
+    /// Whether the window is visible and neither a dock nor the desktop
+    fn is_client_window(&self, window: u32) -> bool {
+        self.is_window_visible(window)
+            && !self.get_window_types(window).iter().any(|window_type| {
+                *window_type == self.atoms._NET_WM_WINDOW_TYPE_DOCK
+                    || *window_type == self.atoms._NET_WM_WINDOW_TYPE_DESKTOP
+            })
+    }
+
+    fn get_parent(&self, window: u32) -> Option<u32> {
+        self.connection
+            .query_tree(window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.parent)
+    }
+
+    fn get_string_property(&self, window: u32, property: u32, property_type: u32) -> String {
+        self.connection
+            .get_property(false, window, property, property_type, 0, 1024)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+            .unwrap_or_default()
+    }
+
+    fn get_window_types(&self, window: u32) -> Vec<u32> {
+        self.connection
+            .get_property(
+                false,
+                window,
+                self.atoms._NET_WM_WINDOW_TYPE,
+                AtomEnum::ATOM,
+                0,
+                32,
+            )
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().map(|types| types.collect()))
+            .unwrap_or_default()
+    }
+
+    fn get_transient_for(&self, window: u32) -> Option<u32> {
+        self.connection
+            .get_property(
+                false,
+                window,
+                AtomEnum::WM_TRANSIENT_FOR,
+                AtomEnum::WINDOW,
+                0,
+                1,
+            )
+            .ok()?
+            .reply()
+            .ok()?
+            .value32()?
+            .next()
+    }
+
+    /// Get the area of the screen left by the docks and panels, if a window manager publishes it
+    fn get_work_area(&self) -> Option<Rect> {
+        let reply = self
+            .connection
+            .get_property(
+                false,
+                self.root,
+                self.atoms._NET_WORKAREA,
+                AtomEnum::CARDINAL,
+                0,
+                4,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+
+        let values: Vec<u32> = reply.value32()?.collect();
+        match values[..] {
+            [x, y, width, height] => Some(Rect::new(
+                x as i32,
+                y as i32,
+                (x + width) as i32,
+                (y + height) as i32,
+            )),
+            _ => None,
+        }
+    }
+
+    fn get_randr_monitors(&self) -> Vec<Monitor> {
+        let Some(reply) = self
+            .connection
+            .randr_get_monitors(self.root, true)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+        else {
+            return Vec::new();
+        };
+
+        reply
+            .monitors
+            .iter()
+            .map(|monitor| {
+                let name = self
+                    .connection
+                    .get_atom_name(monitor.name)
+                    .ok()
+                    .and_then(|cookie| cookie.reply().ok())
+                    .map(|reply| String::from_utf8_lossy(&reply.name).into_owned())
+                    .unwrap_or_default();
+                let x = monitor.x as i32;
+                let y = monitor.y as i32;
+
+                Monitor::new(
+                    &name,
+                    monitor.name as isize,
+                    Rect::new(x, y, x + monitor.width as i32, y + monitor.height as i32),
+                )
+            })
+            .collect()
+    }
+
+    fn get_xinerama_monitors(&self) -> Vec<Monitor> {
+        let Some(reply) = self
+            .connection
+            .xinerama_query_screens()
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+        else {
+            return Vec::new();
+        };
+
+        reply
+            .screen_info
+            .iter()
+            .enumerate()
+            .map(|(index, screen)| {
+                let x = screen.x_org as i32;
+                let y = screen.y_org as i32;
+
+                Monitor::new(
+                    &format!("screen-{}", index),
+                    index as isize + 1,
+                    Rect::new(x, y, x + screen.width as i32, y + screen.height as i32),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for X11WindowSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Window events read from the connection, so the source needs its own `X11WindowSystem`
+impl WindowEventSource for X11WindowSystem {
+    fn listen(&self, mut callback: WindowEventCallback) {
+        let client_mask = EventMask::STRUCTURE_NOTIFY | EventMask::PROPERTY_CHANGE;
+        self.select_window_events(
+            self.root,
+            EventMask::SUBSTRUCTURE_NOTIFY | EventMask::PROPERTY_CHANGE,
+        );
+
+        // The windows listed by the running window manager are followed through its client list
+        let mut clients = self.get_client_list().unwrap_or_default();
+        for client in clients.iter() {
+            self.select_window_events(*client, client_mask);
+        }
+        if clients.is_empty() {
+            for window in self.get_root_childrens() {
+                self.select_window_events(window, client_mask);
+            }
+        }
+        let _ = self.connection.flush();
+
+        while let Ok(event) = self.connection.wait_for_event() {
+            let window_event = match event {
+                Event::PropertyNotify(event)
+                    if event.window == self.root && event.atom == self.atoms._NET_CLIENT_LIST =>
+                {
+                    let new_clients = self.get_client_list().unwrap_or_default();
+                    for client in new_clients
+                        .iter()
+                        .filter(|client| !clients.contains(client))
+                    {
+                        self.select_window_events(*client, client_mask);
+                        callback(WindowEvent::Created(*client as isize));
+                    }
+                    for client in clients
+                        .iter()
+                        .filter(|client| !new_clients.contains(client))
+                    {
+                        callback(WindowEvent::Destroyed(*client as isize));
+                    }
+                    clients = new_clients;
+                    let _ = self.connection.flush();
+                    None
+                }
+                Event::PropertyNotify(event)
+                    if event.window == self.root && event.atom == self.atoms._NET_ACTIVE_WINDOW =>
+                {
+                    self.get_active_window()
+                        .map(|window| WindowEvent::FocusChanged(window as isize))
+                }
+                Event::PropertyNotify(event)
+                    if event.atom == self.atoms._NET_WM_NAME
+                        || event.atom == u32::from(AtomEnum::WM_NAME) =>
+                {
+                    Some(WindowEvent::TitleChanged(event.window as isize))
+                }
+                Event::ConfigureNotify(event) => {
+                    Some(WindowEvent::MovedOrResized(event.window as isize))
+                }
+                Event::MapNotify(event) => {
+                    // Without a window manager, the titles are followed on the windows themselves
+                    self.select_window_events(event.window, client_mask);
+                    let _ = self.connection.flush();
+                    Some(WindowEvent::Created(event.window as isize))
+                }
+                // The iconified windows are unmapped
+                Event::UnmapNotify(event) => Some(WindowEvent::Minimized(event.window as isize)),
+                Event::DestroyNotify(event) => Some(WindowEvent::Destroyed(event.window as isize)),
+                _ => None,
+            };
+
+            if let Some(window_event) = window_event {
+                callback(window_event);
+            }
+        }
+    }
+}
+
+impl WindowSystem for X11WindowSystem {
+    fn get_windows(&self) -> Vec<isize> {
+        self.get_client_list()
+            .unwrap_or_else(|| self.get_root_childrens())
+            .into_iter()
+            .filter(|window| self.is_client_window(*window))
+            .map(|window| window as isize)
+            .collect()
+    }
+
+    fn is_manageable(&self, hwnd: isize) -> bool {
+        let window = hwnd as u32;
+        // The running window manager reparents its clients into frames
+        let is_top_level = match self.get_client_list() {
+            Some(clients) => clients.contains(&window),
+            None => self.get_parent(window) == Some(self.root),
+        };
+
+        is_top_level && self.is_client_window(window)
+    }
+
+    fn get_window_title(&self, hwnd: isize) -> String {
+        let title =
+            self.get_string_property(hwnd as u32, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING);
+        if !title.is_empty() {
+            return title;
+        }
+
+        self.get_string_property(
+            hwnd as u32,
+            AtomEnum::WM_NAME.into(),
+            AtomEnum::STRING.into(),
+        )
+    }
+
+    fn get_window_class(&self, hwnd: isize) -> Option<String> {
+        // WM_CLASS holds the instance name then the class name, both null terminated
+        let wm_class = self.get_string_property(
+            hwnd as u32,
+            AtomEnum::WM_CLASS.into(),
+            AtomEnum::STRING.into(),
+        );
+
+        wm_class
+            .split('\0')
+            .nth(1)
+            .filter(|class| !class.is_empty())
+            .map(str::to_owned)
+    }
+
+    fn get_window_process_id(&self, hwnd: isize) -> Option<u32> {
+        self.connection
+            .get_property(
+                false,
+                hwnd as u32,
+                self.atoms._NET_WM_PID,
+                AtomEnum::CARDINAL,
+                0,
+                1,
+            )
+            .ok()?
+            .reply()
+            .ok()?
+            .value32()?
+            .next()
+    }
+
+    fn get_process_executable(&self, process_id: u32) -> Option<String> {
+        std::fs::read_link(format!("/proc/{}/exe", process_id))
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned())
+    }
+
+    fn get_window_rect(&self, hwnd: isize) -> Option<Rect> {
+        let window = hwnd as u32;
+        let geometry = self.connection.get_geometry(window).ok()?.reply().ok()?;
+        let position = self
+            .connection
+            .translate_coordinates(window, self.root, 0, 0)
+            .ok()?
+            .reply()
+            .ok()?;
+
+        let x = position.dst_x as i32;
+        let y = position.dst_y as i32;
+        Some(Rect::new(
+            x,
+            y,
+            x + geometry.width as i32,
+            y + geometry.height as i32,
+        ))
+    }
+
+    fn set_window_pos(&self, hwnd: isize, x: i32, y: i32, width: i32, height: i32) -> bool {
+        let values = ConfigureWindowAux::new()
+            .x(x)
+            .y(y)
+            .width(width.max(1) as u32)
+            .height(height.max(1) as u32);
+
+        self.connection
+            .configure_window(hwnd as u32, &values)
+            .is_ok()
+            && self.connection.flush().is_ok()
+    }
+
+    fn put_on_top(&self, hwnd: isize) {
+        let values = ConfigureWindowAux::new().stack_mode(StackMode::ABOVE);
+
+        if self
+            .connection
+            .configure_window(hwnd as u32, &values)
+            .is_ok()
+        {
+            let _ = self.connection.flush();
+        }
+    }
+
+    fn get_monitors(&self) -> Vec<Monitor> {
+        let mut monitors = self.get_randr_monitors();
+        if monitors.is_empty() {
+            monitors = self.get_xinerama_monitors();
+        }
+
+        if !monitors.is_empty() {
+            let work_area = self.get_work_area();
+            return monitors
+                .into_iter()
+                .map(|monitor| match work_area {
+                    Some(work_area) => {
+                        let monitor_work_area = monitor.rect.intersect(&work_area);
+                        monitor.with_work_area(monitor_work_area)
+                    }
+                    None => monitor,
+                })
+                .collect();
+        }
+
+        // Neither extension is available, the whole root window is the only monitor
+        let screen = &self.connection.setup().roots[self.screen_index];
+        vec![Monitor::new(
+            "screen-0",
+            1,
+            Rect::new(
+                0,
+                0,
+                screen.width_in_pixels as i32,
+                screen.height_in_pixels as i32,
+            ),
+        )]
+    }
+
+    fn get_monitor_from_window(&self, hwnd: isize) -> isize {
+        match self.get_window_rect(hwnd) {
+            Some(rect) => get_monitor_from_rect(&self.get_monitors(), &rect),
+            None => 0,
+        }
+    }
+
+    fn get_cursor_position(&self) -> (i32, i32) {
+        self.connection
+            .query_pointer(self.root)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| (reply.root_x as i32, reply.root_y as i32))
+            .unwrap_or((0, 0))
+    }
+
+    fn get_foreground_window(&self) -> Option<isize> {
+        self.get_active_window().map(|window| window as isize)
+    }
+
+    fn set_foreground_window(&self, hwnd: isize) {
+        let window = hwnd as u32;
+        if self.get_client_list().is_some() {
+            // Ask the running window manager, the source indication 2 stands for a pager
+            let event =
+                ClientMessageEvent::new(32, window, self.atoms._NET_ACTIVE_WINDOW, [2, 0, 0, 0, 0]);
+            let _ = self.connection.send_event(
+                false,
+                self.root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            );
+        } else {
+            let _ =
+                self.connection
+                    .set_input_focus(InputFocus::PARENT, window, x11rb::CURRENT_TIME);
+        }
+        let _ = self.connection.flush();
+    }
+
+    fn is_dialog(&self, hwnd: isize) -> bool {
+        let window = hwnd as u32;
+        if self
+            .get_transient_for(window)
+            .is_some_and(|owner| owner != 0)
+        {
+            return true;
+        }
+
+        self.get_window_types(window).iter().any(|window_type| {
+            *window_type == self.atoms._NET_WM_WINDOW_TYPE_DIALOG
+                || *window_type == self.atoms._NET_WM_WINDOW_TYPE_UTILITY
+        })
+    }
+}
@@ -0,0 +1,291 @@
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::scratchpad::DEFAULT_SCRATCHPAD;
+use crate::windows::{InsertPosition, TilingMode};
+
+/// Properties of a window the rules are matched against
+#[derive(Debug, Clone, Default)]
+pub struct WindowProperties {
+    pub title: String,
+    pub class: Option<String>,
+    pub process_id: Option<u32>,
+    /// Path of the executable run by the process owning the window
+    pub executable: Option<String>,
+    /// Name of the monitor displaying the window
+    pub monitor: Option<String>,
+}
+
+/// Which rule wins when several matching rules set the same effect
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum RuleMatching {
+    #[default]
+    First,
+    Last,
+}
+impl RuleMatching {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "first" => Some(RuleMatching::First),
+            "last" => Some(RuleMatching::Last),
+            _ => None,
+        }
+    }
+}
+
+/// Every criterion set must match, a criterion on a property the window system does not know never matches
+#[derive(Debug, Clone, Default)]
+pub struct RuleCriteria {
+    pub title: Option<Regex>,
+    pub class: Option<Regex>,
+    /// Matched against the file name of the executable
+    pub executable: Option<Regex>,
+    pub monitor: Option<String>,
+}
+impl RuleCriteria {
+    pub fn matches(&self, properties: &WindowProperties) -> bool {
+        fn is_match(regex: &Option<Regex>, value: Option<&str>) -> bool {
+            match (regex, value) {
+                (None, _) => true,
+                (Some(regex), Some(value)) => regex.is_match(value),
+                (Some(_), None) => false,
+            }
+        }
+
+        let executable_name = properties
+            .executable
+            .as_deref()
+            .and_then(|executable| Path::new(executable).file_name())
+            .map(|name| name.to_string_lossy());
+
+        is_match(&self.title, Some(&properties.title))
+            && is_match(&self.class, properties.class.as_deref())
+            && is_match(&self.executable, executable_name.as_deref())
+            && match &self.monitor {
+                Some(monitor) => properties.monitor.as_ref() == Some(monitor),
+                None => true,
+            }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleEffect {
+    Managed,
+    Exclude,
+    Float,
+    Monocle,
+    /// The sticky windows float, unless they reserve their place in the tiling
+    Sticky {
+        tiled: bool,
+    },
+    Scratchpad(String),
+    Workspace(String),
+    Insert(InsertPosition),
+}
+impl RuleEffect {
+    /// Parse `managed`, `exclude`, `float`, `monocle`, `sticky`, `sticky_tiled`, `scratchpad`,
+    /// `workspace=<name>` or `insert=<before|after|first|last>`
+    ///
+    /// The rules send the windows to the default scratchpad, the only one the hotkeys summon
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.split_once('=') {
+            Some(("workspace", value)) if !value.is_empty() => {
+                Some(RuleEffect::Workspace(value.to_owned()))
+            }
+            Some(("insert", value)) => InsertPosition::from_name(value).map(RuleEffect::Insert),
+            Some(_) => None,
+            None => match name {
+                "managed" => Some(RuleEffect::Managed),
+                "exclude" => Some(RuleEffect::Exclude),
+                "float" => Some(RuleEffect::Float),
+                "monocle" => Some(RuleEffect::Monocle),
+                "sticky" => Some(RuleEffect::Sticky { tiled: false }),
+                "sticky_tiled" => Some(RuleEffect::Sticky { tiled: true }),
+                "scratchpad" => Some(RuleEffect::Scratchpad(DEFAULT_SCRATCHPAD.to_owned())),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub criteria: RuleCriteria,
+    pub effects: Vec<RuleEffect>,
+}
+
+/// Outcome of the rules matching a window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleEffects {
+    pub managed: bool,
+    pub excluded: bool,
+    pub mode: Option<TilingMode>,
+    pub sticky: bool,
+    pub scratchpad: Option<String>,
+    /// Name of the workspace the window opens on instead of the visible one of its monitor
+    pub workspace: Option<String>,
+    pub insert_position: Option<InsertPosition>,
+}
+impl RuleEffects {
+    /// Whether the window is managed, an exclusion wins over any number of managed rules
+    ///
+    /// An effect placing the window implies it is managed, so it does not need a `managed` rule too
+    pub fn is_managed(&self) -> bool {
+        let is_placed = self.mode.is_some()
+            || self.sticky
+            || self.scratchpad.is_some()
+            || self.workspace.is_some()
+            || self.insert_position.is_some();
+
+        (self.managed || is_placed) && !self.excluded
+    }
+}
+
+/// Combine the effects of every rule matching the window
+///
+/// The rules are applied from the one that loses to the one that wins, so each effect keeps the
+/// value of the winning rule setting it
+pub fn get_rule_effects(
+    rules: &[Rule],
+    matching: RuleMatching,
+    properties: &WindowProperties,
+) -> RuleEffects {
+    let matching_rules = rules
+        .iter()
+        .filter(|rule| rule.criteria.matches(properties));
+    let matching_rules: Box<dyn Iterator<Item = &Rule>> = match matching {
+        RuleMatching::First => Box::new(matching_rules.rev()),
+        RuleMatching::Last => Box::new(matching_rules),
+    };
+
+    let mut effects = RuleEffects::default();
+    for rule in matching_rules {
+        for effect in rule.effects.iter() {
+            match effect {
+                RuleEffect::Managed => effects.managed = true,
+                RuleEffect::Exclude => effects.excluded = true,
+                RuleEffect::Float => effects.mode = Some(TilingMode::Floating),
+                RuleEffect::Monocle => effects.mode = Some(TilingMode::Monocle),
+                RuleEffect::Sticky { tiled } => {
+                    effects.sticky = true;
+                    effects.mode = Some(if *tiled {
+                        TilingMode::Managed
+                    } else {
+                        TilingMode::Floating
+                    });
+                }
+                RuleEffect::Scratchpad(name) => effects.scratchpad = Some(name.clone()),
+                RuleEffect::Workspace(name) => effects.workspace = Some(name.clone()),
+                RuleEffect::Insert(position) => effects.insert_position = Some(*position),
+            }
+        }
+    }
+
+    effects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_rule(effects: Vec<RuleEffect>, title: &str) -> Rule {
+        Rule {
+            criteria: RuleCriteria {
+                title: Some(Regex::new(title).unwrap()),
+                ..RuleCriteria::default()
+            },
+            effects,
+        }
+    }
+
+    fn create_properties(title: &str) -> WindowProperties {
+        WindowProperties {
+            title: title.to_owned(),
+            ..WindowProperties::default()
+        }
+    }
+
+    #[test]
+    fn first_or_last_matching_rule_wins() {
+        let rules = vec![
+            create_rule(vec![RuleEffect::Float], "term"),
+            create_rule(vec![RuleEffect::Monocle], ".*"),
+        ];
+        let properties = create_properties("term");
+
+        let first_effects = get_rule_effects(&rules, RuleMatching::First, &properties);
+        assert_eq!(first_effects.mode, Some(TilingMode::Floating));
+        let last_effects = get_rule_effects(&rules, RuleMatching::Last, &properties);
+        assert_eq!(last_effects.mode, Some(TilingMode::Monocle));
+    }
+
+    #[test]
+    fn exclude_wins_over_managed_and_placements() {
+        let rules = vec![
+            create_rule(vec![RuleEffect::Managed, RuleEffect::Float], ".*"),
+            create_rule(vec![RuleEffect::Exclude], "^excluded$"),
+        ];
+
+        for matching in [RuleMatching::First, RuleMatching::Last] {
+            assert!(
+                !get_rule_effects(&rules, matching, &create_properties("excluded")).is_managed()
+            );
+            assert!(get_rule_effects(&rules, matching, &create_properties("other")).is_managed());
+        }
+    }
+
+    #[test]
+    fn placement_effects_imply_managed() {
+        let rules = vec![create_rule(
+            vec![RuleEffect::Workspace("two".to_owned())],
+            ".*",
+        )];
+
+        let effects = get_rule_effects(&rules, RuleMatching::First, &create_properties("any"));
+        assert!(effects.is_managed());
+        assert!(!RuleEffects::default().is_managed());
+    }
+
+    #[test]
+    fn criteria_match_every_property_set() {
+        let criteria = RuleCriteria {
+            class: Some(Regex::new("^Term$").unwrap()),
+            executable: Some(Regex::new("^term\\.exe$").unwrap()),
+            monitor: Some("main".to_owned()),
+            ..RuleCriteria::default()
+        };
+        let mut properties = WindowProperties {
+            title: "shell".to_owned(),
+            class: Some("Term".to_owned()),
+            executable: Some("/usr/bin/term.exe".to_owned()),
+            monitor: Some("main".to_owned()),
+            ..WindowProperties::default()
+        };
+        assert!(criteria.matches(&properties));
+
+        properties.monitor = Some("side".to_owned());
+        assert!(!criteria.matches(&properties));
+        properties.monitor = Some("main".to_owned());
+        properties.class = None;
+        assert!(!criteria.matches(&properties));
+    }
+
+    #[test]
+    fn effect_names_are_parsed() {
+        assert_eq!(
+            RuleEffect::from_name("sticky_tiled"),
+            Some(RuleEffect::Sticky { tiled: true })
+        );
+        assert_eq!(
+            RuleEffect::from_name("workspace=two"),
+            Some(RuleEffect::Workspace("two".to_owned()))
+        );
+        assert_eq!(
+            RuleEffect::from_name("insert=last"),
+            Some(RuleEffect::Insert(InsertPosition::Last))
+        );
+        assert_eq!(RuleEffect::from_name("workspace="), None);
+        assert_eq!(RuleEffect::from_name("unknown"), None);
+    }
+}
@@ -0,0 +1,19 @@
+use crate::windows::Window;
+
+/// Scratchpad used by the hotkeys
+pub const DEFAULT_SCRATCHPAD: &str = "default";
+
+/// Window hidden from every workspace until its scratchpad is summoned
+#[derive(Debug, Clone)]
+pub struct ScratchpadWindow {
+    pub name: String,
+    pub window: Window,
+}
+impl ScratchpadWindow {
+    pub fn new(name: &str, window: Window) -> Self {
+        Self {
+            name: name.to_owned(),
+            window,
+        }
+    }
+}
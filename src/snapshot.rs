@@ -0,0 +1,146 @@
+use crate::monitor::Rect;
+use crate::platform::WindowSystem;
+
+/// What is known about a window when the snapshot is taken
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowState {
+    pub hwnd: isize,
+    pub title: String,
+    pub rect: Option<Rect>,
+}
+
+/// Visible top-level windows of the window system at a given time
+#[derive(Debug, Clone, Default)]
+pub struct WindowSnapshot {
+    windows: Vec<WindowState>,
+    foreground_window: Option<isize>,
+}
+impl WindowSnapshot {
+    pub fn capture<S: WindowSystem>(system: &S) -> Self {
+        let windows = system
+            .get_windows()
+            .into_iter()
+            .map(|hwnd| WindowState {
+                hwnd,
+                title: system.get_window_title(hwnd),
+                rect: system.get_window_rect(hwnd),
+            })
+            .collect();
+
+        Self {
+            windows,
+            foreground_window: system.get_foreground_window(),
+        }
+    }
+
+    /// Keep the windows matching the predicate, the next diff reports the other ones as added
+    pub fn retain<F: FnMut(isize) -> bool>(&mut self, mut predicate: F) {
+        self.windows.retain(|window| predicate(window.hwnd));
+    }
+
+    fn get(&self, hwnd: isize) -> Option<&WindowState> {
+        self.windows.iter().find(|window| window.hwnd == hwnd)
+    }
+
+    /// Compare the windows of both snapshots by handle
+    pub fn diff(&self, next: &WindowSnapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+
+        for window in next.windows.iter() {
+            match self.get(window.hwnd) {
+                None => diff.added.push(window.hwnd),
+                Some(previous) => {
+                    if previous.title != window.title {
+                        diff.retitled.push(window.hwnd);
+                    }
+                    if previous.rect != window.rect {
+                        diff.moved.push(window.hwnd);
+                    }
+                }
+            }
+        }
+        diff.removed = self
+            .windows
+            .iter()
+            .filter(|window| next.get(window.hwnd).is_none())
+            .map(|window| window.hwnd)
+            .collect();
+        if next.foreground_window != self.foreground_window {
+            diff.focused = next.foreground_window;
+        }
+
+        diff
+    }
+}
+
+/// Windows that changed between two snapshots
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDiff {
+    pub added: Vec<isize>,
+    pub removed: Vec<isize>,
+    pub retitled: Vec<isize>,
+    pub moved: Vec<isize>,
+    /// New foreground window
+    pub focused: Option<isize>,
+}
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.retitled.is_empty()
+            && self.moved.is_empty()
+            && self.focused.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::fake::FakeWindowSystem;
+
+    #[test]
+    fn diff_reports_a_window_closed_while_another_opens() {
+        let system = FakeWindowSystem::new();
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        system.open_window("kept", monitor);
+        let closed = system.open_window("closed", monitor);
+        let previous = WindowSnapshot::capture(&system);
+
+        system.close_window(closed);
+        let opened = system.open_window("opened", monitor);
+        let diff = previous.diff(&WindowSnapshot::capture(&system));
+
+        assert_eq!(diff.added, vec![opened]);
+        assert_eq!(diff.removed, vec![closed]);
+        assert!(diff.retitled.is_empty());
+        assert!(diff.moved.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_retitled_and_moved_windows() {
+        let system = FakeWindowSystem::new();
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        let retitled = system.open_window("before", monitor);
+        let moved = system.open_window("moved", monitor);
+        let previous = WindowSnapshot::capture(&system);
+
+        system.set_title(retitled, "after");
+        system.set_window_pos(moved, 10, 10, 640, 480);
+        let diff = previous.diff(&WindowSnapshot::capture(&system));
+
+        assert_eq!(diff.retitled, vec![retitled]);
+        assert_eq!(diff.moved, vec![moved]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+    }
+
+    #[test]
+    fn diff_of_identical_snapshots_is_empty() {
+        let system = FakeWindowSystem::new();
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        system.open_window("window", monitor);
+
+        let snapshot = WindowSnapshot::capture(&system);
+
+        assert!(snapshot.diff(&snapshot.clone()).is_empty());
+    }
+}
@@ -6,10 +6,26 @@ pub enum TilingDirection {
     Horizontal,
 }
 
+/// How the childrens of a node share its area
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ContainerMode {
+    /// The childrens are placed next to each other following their direction
+    Split,
+    /// Only the active children is shown, the other ones are moved out of the monitor
+    Tabbed,
+    /// Only the active children is shown, the other ones are kept behind it
+    Stacked,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Node<T> {
     pub value: T,
     pub direction: TilingDirection,
+    /// Share of the parent node taken by this node, relative to its siblings
+    pub weight: f32,
+    pub container_mode: ContainerMode,
+    /// Index of the children shown by the tabbed and stacked containers
+    pub active_children: usize,
     pub childrens: Vec<Box<Node<T>>>,
 }
 impl<T> Node<T> {
@@ -17,6 +33,9 @@ impl<T> Node<T> {
         Self {
             value,
             direction,
+            weight: 1.0,
+            container_mode: ContainerMode::Split,
+            active_children: 0,
             childrens: Vec::new(),
         }
     }
@@ -26,7 +45,67 @@ impl<T> Node<T> {
             .insert(index, Box::new(Node::new(new_val, direction)));
     }
 
+    /// Give back the same share to every node of the tree
+    pub fn reset_weights(&mut self) {
+        self.weight = 1.0;
+        for children in self.childrens.iter_mut() {
+            children.reset_weights();
+        }
+    }
+
+    pub fn get_active_children(&self) -> Option<&Node<T>> {
+        let index = self
+            .active_children
+            .min(self.childrens.len().checked_sub(1)?);
+        self.childrens.get(index).map(|children| children.as_ref())
+    }
+
     pub fn is_leaf(&self) -> bool {
-        return self.childrens.is_empty();
+        self.childrens.is_empty()
+    }
+
+    /// Get the paths of child indexes leading to every leaf under this node, in depth-first order
+    pub fn leaf_paths(&self) -> Vec<Vec<usize>> {
+        let mut paths = Vec::new();
+        self.collect_leaf_paths(&mut Vec::new(), &mut paths);
+
+        paths
+    }
+
+    fn collect_leaf_paths(&self, current_path: &mut Vec<usize>, paths: &mut Vec<Vec<usize>>) {
+        for (index, children) in self.childrens.iter().enumerate() {
+            current_path.push(index);
+            if children.is_leaf() {
+                paths.push(current_path.clone());
+            } else {
+                children.collect_leaf_paths(current_path, paths);
+            }
+            current_path.pop();
+        }
+    }
+
+    /// Get the path of the first leaf whose value matches the predicate
+    pub fn find_leaf_path<F: Fn(&T) -> bool>(&self, predicate: F) -> Option<Vec<usize>> {
+        self.leaf_paths().into_iter().find(|path| {
+            self.get(path)
+                .map(|node| predicate(&node.value))
+                .unwrap_or(false)
+        })
+    }
+
+    pub fn get(&self, path: &[usize]) -> Option<&Node<T>> {
+        match path.split_first() {
+            Some((index, remaining_path)) => self.childrens.get(*index)?.get(remaining_path),
+            None => Some(self),
+        }
+    }
+
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut Node<T>> {
+        match path.split_first() {
+            Some((index, remaining_path)) => {
+                self.childrens.get_mut(*index)?.get_mut(remaining_path)
+            }
+            None => Some(self),
+        }
     }
 }
@@ -1,44 +1,103 @@
-use windows_sys::Win32::UI::WindowsAndMessaging::{BringWindowToTop, GetWindowTextW, SetWindowPos};
+use crate::monitor::Rect;
+use crate::rules::WindowProperties;
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TilingMode {
     Managed,
     Monocle,
+    /// Skipped by the layouts, the window keeps the position and size chosen by the user
+    Floating,
 }
 
-#[derive(Clone)]
+/// Where a window gets placed the first time it floats
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FloatingPlacement {
+    Center,
+    UnderCursor,
+    Cascade,
+}
+impl FloatingPlacement {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "center" => Some(FloatingPlacement::Center),
+            "cursor" => Some(FloatingPlacement::UnderCursor),
+            "cascade" => Some(FloatingPlacement::Cascade),
+            _ => None,
+        }
+    }
+}
+
+/// Where a new window is inserted in the tree of its workspace
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum InsertPosition {
+    #[default]
+    BeforeCurrent,
+    AfterCurrent,
+    First,
+    Last,
+}
+impl InsertPosition {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "before" => Some(InsertPosition::BeforeCurrent),
+            "after" => Some(InsertPosition::AfterCurrent),
+            "first" => Some(InsertPosition::First),
+            "last" => Some(InsertPosition::Last),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Window {
     pub title: String,
     pub hwnd: isize,
+    pub class: Option<String>,
+    pub process_id: Option<u32>,
+    pub executable: Option<String>,
     pub mode: TilingMode,
+    /// Geometry the window had the last time it was floating
+    pub floating_rect: Option<Rect>,
+    /// Whether the window stays on the monitor when it switches workspaces
+    pub sticky: bool,
+    /// Whether the window is minimised, it keeps its place in the tree but is left out of the layout
+    pub minimized: bool,
 }
 impl Window {
     pub fn new(title: &str, hwnd: isize) -> Self {
         Self {
             title: title.to_owned(),
             hwnd,
+            class: None,
+            process_id: None,
+            executable: None,
             mode: TilingMode::Managed,
+            floating_rect: None,
+            sticky: false,
+            minimized: false,
         }
     }
 
-    pub fn set_mode(&mut self, mode: TilingMode) {
-        self.mode = mode;
+    /// Create the window with the properties captured when it was discovered
+    pub fn from_properties(hwnd: isize, properties: &WindowProperties) -> Self {
+        Self {
+            class: properties.class.clone(),
+            process_id: properties.process_id,
+            executable: properties.executable.clone(),
+            ..Self::new(&properties.title, hwnd)
+        }
     }
 
-    pub fn set_window_pos(&self, x: i32, y: i32, width: i32, height: i32) -> bool {
-        unsafe { SetWindowPos(self.hwnd, 0, x, y, width, height, 0x0040) == 1 }
+    /// Placeholder value of the tree nodes grouping other windows
+    pub fn container() -> Self {
+        Self::new("()", 0)
     }
 
-    pub fn put_on_top(&self) {
-        unsafe { BringWindowToTop(self.hwnd) };
+    pub fn is_container(&self) -> bool {
+        self.hwnd == 0
     }
-}
 
-impl Window {
-    pub fn get_window_title(hwnd: isize) -> String {
-        let mut text: [u16; 512] = [0; 512];
-        let len = unsafe { GetWindowTextW(hwnd, text.as_mut_ptr(), text.len() as i32) };
-
-        String::from_utf16_lossy(&text[..len as usize])
+    pub fn set_mode(&mut self, mode: TilingMode) {
+        self.mode = mode;
     }
 }
@@ -1,70 +1,177 @@
 use std::sync::{Arc, Mutex};
 
-use windows_sys::Win32::Foundation::LPARAM;
-use windows_sys::Win32::Graphics::Gdi::HMONITOR;
-use windows_sys::Win32::UI::WindowsAndMessaging::{EnumWindows, IsWindowVisible};
-
-use crate::actions::WorkspaceAction;
+use crate::actions::{WorkspaceAction, WorkspaceTarget};
 use crate::config::Config;
-use crate::monitor::{get_monitor_from_window, Monitors};
-use crate::windows::Window;
+use crate::events::WindowEvent;
+use crate::layout::{get_parked_rect, is_parked_rect};
+use crate::monitor::{get_nearest_in_direction, Direction, Rect};
+use crate::platform::WindowSystem;
+use crate::rules::WindowProperties;
+use crate::scratchpad::ScratchpadWindow;
+use crate::snapshot::{SnapshotDiff, WindowSnapshot};
+use crate::windows::{TilingMode, Window};
 use crate::workspace::Workspace;
 
-pub struct WindowManager {
+pub struct WindowManager<S: WindowSystem> {
     config: Arc<Mutex<Config>>,
+    system: Arc<S>,
     windows: Vec<isize>,
-    workspaces: Vec<Workspace>,
+    workspaces: Vec<Workspace<S>>,
+    scratchpad_windows: Vec<ScratchpadWindow>,
+    /// Windows seen by the last fetch
+    snapshot: WindowSnapshot,
+    /// Window the WM last saw or put in the foreground
+    focused_window: Option<isize>,
+    /// Focused windows of every workspace, from the most to the least recent
+    focus_history: Vec<isize>,
+    /// Position in the history while cycling through it
+    focus_history_cursor: Option<usize>,
 
     current_workspace_index: usize,
+    hovered_monitor_handle: Option<isize>,
 }
-impl WindowManager {
-    pub fn new(config: Arc<Mutex<Config>>) -> Self {
+impl<S: WindowSystem> WindowManager<S> {
+    pub fn new(config: Arc<Mutex<Config>>, system: Arc<S>) -> Self {
         Self {
             config,
+            system,
             windows: Vec::new(),
             workspaces: Vec::new(),
+            scratchpad_windows: Vec::new(),
+            snapshot: WindowSnapshot::default(),
+            focused_window: None,
+            focus_history: Vec::new(),
+            focus_history_cursor: None,
 
             current_workspace_index: 0,
+            hovered_monitor_handle: None,
         }
     }
 
     pub fn get_monitors(&mut self) {
-        let monitors = Monitors::get_monitors_list();
+        let monitors = self.system.get_monitors();
 
         let config = self.config.lock().unwrap();
-        let workspaces_monitor_name = config.get_workspaces_monitors();
-
-        self.workspaces.clear();
-        self.windows.clear();
-        for workspace_monitor in workspaces_monitor_name.iter() {
-            for monitor in monitors.iter() {
-                if workspace_monitor.clone() == monitor.name {
-                    self.workspaces.push(Workspace::new(monitor.clone()));
-                }
+        let workspaces_config = config.get_workspaces();
+        let layout_options = config.get_layout_options();
+
+        // The windows, scratchpads and focus history outlive the workspaces rebuilt by a reload
+        let mut previous_workspaces = std::mem::take(&mut self.workspaces);
+        for workspace_config in workspaces_config.iter() {
+            // Use the first connected monitor among the main one and the fallbacks
+            let Some(monitor) = workspace_config.get_monitors().find_map(|monitor_name| {
+                monitors
+                    .iter()
+                    .find(|monitor| monitor.name == *monitor_name)
+            }) else {
+                continue;
+            };
+
+            let mut monitor = monitor.clone();
+            monitor.reserve_struts(&config.get_struts(&monitor.name));
+
+            // Only the first workspace of each monitor is shown
+            let is_first_of_monitor = !self
+                .workspaces
+                .iter()
+                .any(|workspace| workspace.is_on_monitor(monitor.monitor_handle));
+
+            let mut workspace_layout_options = layout_options.clone();
+            if let Some(layout) = workspace_config.layout {
+                workspace_layout_options.layout = layout;
+            }
+
+            let mut workspace = Workspace::new(
+                &workspace_config.get_name(),
+                monitor,
+                Arc::clone(&self.system),
+                workspace_layout_options,
+            );
+            if !is_first_of_monitor {
+                workspace.hide();
             }
+            self.workspaces.push(workspace);
         }
+        drop(config);
+
+        self.carry_windows_over(&mut previous_workspaces);
+        // The next fetch matches the windows left unmanaged against the reloaded rules
+        let windows = &self.windows;
+        self.snapshot.retain(|hwnd| windows.contains(&hwnd));
+        self.current_workspace_index = 0;
+        self.hovered_monitor_handle = None;
     }
 
-    fn get_windows(&self) -> Vec<isize> {
-        let mut windows: Vec<isize> = Vec::new();
+    /// Move the windows of the previous workspaces to the new ones with the same name, or index
+    ///
+    /// The windows are forgotten when no workspace is left, the next fetch manages them again
+    fn carry_windows_over(&mut self, previous_workspaces: &mut [Workspace<S>]) {
+        for (index, previous_workspace) in previous_workspaces.iter_mut().enumerate() {
+            let workspace_index = self
+                .workspaces
+                .iter()
+                .position(|workspace| workspace.get_name() == previous_workspace.get_name())
+                .or_else(|| (index < self.workspaces.len()).then_some(index))
+                .or_else(|| (!self.workspaces.is_empty()).then_some(0));
 
-        unsafe {
-            EnumWindows(Some(get_window_def), &mut windows as *mut _ as LPARAM);
+            match workspace_index {
+                Some(workspace_index) => {
+                    self.workspaces[workspace_index].take_windows_from(previous_workspace)
+                }
+                None => {
+                    for path in previous_workspace.windows.leaf_paths() {
+                        if let Some(node) = previous_workspace.windows.get(&path) {
+                            let hwnd = node.value.hwnd;
+                            self.forget_window(hwnd);
+                        }
+                    }
+                }
+            }
         }
+    }
 
-        windows
+    /// Put the windows of every workspace and scratchpad back on the monitors, before the WM quits
+    pub fn restore_all(&mut self) {
+        for workspace in self.workspaces.iter_mut() {
+            workspace.restore_windows();
+        }
+
+        for scratchpad_window in self.scratchpad_windows.iter() {
+            if let Some(rect) = scratchpad_window.window.floating_rect {
+                self.system.set_window_pos(
+                    scratchpad_window.window.hwnd,
+                    rect.left,
+                    rect.top,
+                    rect.width(),
+                    rect.height(),
+                );
+            }
+        }
     }
 
-    fn get_managed_windows(&self, windows: &Vec<isize>) -> Vec<isize> {
+    fn get_managed_windows(&self, windows: &[isize]) -> Vec<isize> {
         let mut managed_windows = Vec::new();
-        for window_hwnd in windows.clone() {
-            let title = Window::get_window_title(window_hwnd);
+        for window_hwnd in windows.iter().copied() {
+            let title = self.system.get_window_title(window_hwnd);
 
             if title.is_empty() {
                 continue;
             }
 
-            if self.config.lock().unwrap().is_managed(&title) {
+            // The rules are only applied once, the title of a tracked window may change freely
+            if self.windows.contains(&window_hwnd) {
+                managed_windows.push(window_hwnd);
+                continue;
+            }
+
+            let properties = self.get_window_properties(window_hwnd, &title);
+            if self
+                .config
+                .lock()
+                .unwrap()
+                .get_rule_effects(&properties)
+                .is_managed()
+            {
                 managed_windows.push(window_hwnd);
             }
         }
@@ -72,66 +179,338 @@ impl WindowManager {
         managed_windows
     }
 
+    /// Capture what the rules can match about the window
+    fn get_window_properties(&self, hwnd: isize, title: &str) -> WindowProperties {
+        let monitor_handle = self.system.get_monitor_from_window(hwnd);
+        let monitor = self
+            .system
+            .get_monitors()
+            .into_iter()
+            .find(|monitor| monitor.monitor_handle == monitor_handle)
+            .map(|monitor| monitor.name);
+
+        let process_id = self.system.get_window_process_id(hwnd);
+
+        WindowProperties {
+            title: title.to_owned(),
+            class: self.system.get_window_class(hwnd),
+            process_id,
+            executable: process_id
+                .and_then(|process_id| self.system.get_process_executable(process_id)),
+            monitor,
+        }
+    }
+
+    /// Reconcile the workspaces with the windows that changed since the last fetch
     pub fn fetch_windows(&mut self) {
-        let windows = self.get_windows();
+        let snapshot = WindowSnapshot::capture(self.system.as_ref());
+        let diff = self.snapshot.diff(&snapshot);
+        self.snapshot = snapshot;
 
-        let managed_windows = self.get_managed_windows(&windows);
+        let current_window = self.get_current_window_hwnd();
+        let is_focus_change = diff.focused.is_some();
+        self.apply_snapshot_diff(diff);
+        if !is_focus_change && self.get_current_window_hwnd() != current_window {
+            self.focus_current_window();
+        }
+    }
 
-        // Check if a window has been closed
-        if managed_windows.len() < self.windows.len() {
-            let current_windows = self.windows.clone();
-            let windows_to_delete = current_windows
-                .iter()
-                .filter(|item| !managed_windows.contains(item))
-                .into_iter();
+    fn apply_snapshot_diff(&mut self, diff: SnapshotDiff) {
+        // The windows still known by the window system were only minimised or hidden
+        for hwnd in diff.removed {
+            if self.system.get_window_rect(hwnd).is_some() {
+                self.set_window_minimized(hwnd, true);
+            } else {
+                self.forget_window(hwnd);
+            }
+        }
+        for hwnd in diff.added {
+            self.show_window(hwnd);
+        }
+        for hwnd in diff.retitled {
+            self.handle_window_event(WindowEvent::TitleChanged(hwnd));
+        }
+        for hwnd in diff.moved {
+            self.handle_window_event(WindowEvent::MovedOrResized(hwnd));
+        }
+        if let Some(hwnd) = diff.focused {
+            self.follow_foreground_window(hwnd);
+        }
+    }
 
-            for window_to_delete in windows_to_delete {
+    /// React to a change reported by the window system
+    ///
+    /// When a window closes, minimises, moves or opens where a rule places it, the current window
+    /// changes without the window system focusing it, so the WM does
+    pub fn handle_window_event(&mut self, event: WindowEvent) {
+        let current_window = self.get_current_window_hwnd();
+        let is_focus_change = matches!(event, WindowEvent::FocusChanged(_));
+
+        match event {
+            WindowEvent::Created(hwnd) => {
+                if self.system.is_manageable(hwnd) {
+                    self.show_window(hwnd);
+                }
+            }
+            WindowEvent::Destroyed(hwnd) => self.forget_window(hwnd),
+            WindowEvent::Minimized(hwnd) => self.set_window_minimized(hwnd, true),
+            WindowEvent::TitleChanged(hwnd) => {
+                // Some windows only get the title the rules match after they opened
+                if self.windows.contains(&hwnd) {
+                    let title = self.system.get_window_title(hwnd);
+                    self.set_window_title(hwnd, &title);
+                } else if self.system.is_manageable(hwnd) {
+                    self.manage_window(hwnd);
+                }
+            }
+            WindowEvent::MovedOrResized(hwnd) => {
                 for workspace in self.workspaces.iter_mut() {
-                    Workspace::remove_window(&mut workspace.windows, *window_to_delete);
+                    workspace.handle_window_moved(hwnd);
                 }
             }
-            self.windows = managed_windows;
+            WindowEvent::FocusChanged(hwnd) => self.follow_foreground_window(hwnd),
+        }
+
+        if !is_focus_change && self.get_current_window_hwnd() != current_window {
+            self.focus_current_window();
+        }
+    }
+
+    /// Give its place back to a window restored from minimised, or manage a new one
+    fn show_window(&mut self, hwnd: isize) {
+        if self.windows.contains(&hwnd) {
+            self.set_window_minimized(hwnd, false);
+        } else {
+            self.manage_window(hwnd);
+        }
+    }
+
+    fn set_window_minimized(&mut self, hwnd: isize, minimized: bool) {
+        for workspace in self.workspaces.iter_mut() {
+            if workspace.set_window_minimized(hwnd, minimized) {
+                return;
+            }
+        }
+    }
+
+    /// Apply the rules to a window not tracked yet and add it where they place it
+    fn manage_window(&mut self, window_hwnd: isize) {
+        let title = self.system.get_window_title(window_hwnd);
+
+        if title.is_empty() {
             return;
         }
 
-        for window_hwnd in windows {
-            let title = Window::get_window_title(window_hwnd);
+        if self.windows.contains(&window_hwnd) {
+            return;
+        }
 
-            if title.is_empty() {
-                continue;
+        let properties = self.get_window_properties(window_hwnd, &title);
+        let effects = self.config.lock().unwrap().get_rule_effects(&properties);
+        if !effects.is_managed() {
+            return;
+        }
+
+        // The dialogs and pickers keep the geometry their application gives them
+        let mut window = Window::from_properties(window_hwnd, &properties);
+        if self.system.is_dialog(window_hwnd) {
+            window.set_mode(TilingMode::Floating);
+        }
+        if let Some(mode) = effects.mode {
+            window.set_mode(mode);
+        }
+        window.sticky = effects.sticky;
+
+        if let Some(scratchpad_name) = effects.scratchpad {
+            self.windows.push(window_hwnd);
+            self.hide_in_scratchpad(&scratchpad_name, window);
+            return;
+        }
+
+        // Without a target workspace the window opens on the visible workspace of its monitor
+        let monitor = self.system.get_monitor_from_window(window_hwnd);
+        let workspace_index = effects
+            .workspace
+            .and_then(|name| self.get_workspace_index(&WorkspaceTarget::Name(name)))
+            .or_else(|| {
+                self.workspaces.iter().position(|workspace| {
+                    workspace.is_on_monitor(monitor) && workspace.is_visible()
+                })
+            });
+        if let Some(workspace_index) = workspace_index {
+            self.windows.push(window_hwnd);
+            self.workspaces[workspace_index]
+                .add_window_at(window, effects.insert_position.unwrap_or_default());
+        }
+    }
+
+    /// Stop tracking a window that was closed or minimised
+    fn forget_window(&mut self, hwnd: isize) {
+        for workspace in self.workspaces.iter_mut() {
+            workspace.forget_window(hwnd);
+        }
+        self.scratchpad_windows
+            .retain(|scratchpad_window| scratchpad_window.window.hwnd != hwnd);
+        self.windows.retain(|window_hwnd| *window_hwnd != hwnd);
+        if self.focused_window == Some(hwnd) {
+            self.focused_window = None;
+        }
+        if self.focus_history.contains(&hwnd) {
+            self.focus_history
+                .retain(|window_hwnd| *window_hwnd != hwnd);
+            self.focus_history_cursor = None;
+        }
+    }
+
+    /// Make the foreground window the current one of the WM
+    ///
+    /// The windows of the hidden workspaces are ignored, the system may hand them the focus when
+    /// the foreground window closes
+    fn follow_foreground_window(&mut self, hwnd: isize) {
+        self.focused_window = Some(hwnd);
+
+        if let Some(workspace_index) = self
+            .workspaces
+            .iter()
+            .position(|workspace| workspace.is_visible() && workspace.contains_window(hwnd))
+        {
+            self.current_workspace_index = workspace_index;
+            self.workspaces[workspace_index].set_current_window(hwnd);
+            self.record_focus(hwnd);
+        }
+    }
+
+    /// Put the window at the top of the history, unless it was reached by cycling through it
+    fn record_focus(&mut self, hwnd: isize) {
+        if let Some(cursor) = self.focus_history_cursor {
+            if self.focus_history.get(cursor) == Some(&hwnd) {
+                return;
             }
+            self.end_focus_cycle();
+        }
 
-            if self.config.lock().unwrap().is_managed(&title) {
-                let monitor: HMONITOR = get_monitor_from_window(window_hwnd);
+        self.focus_history
+            .retain(|window_hwnd| *window_hwnd != hwnd);
+        self.focus_history.insert(0, hwnd);
+    }
 
-                for workspace in self.workspaces.iter_mut() {
-                    if workspace.is_on_monitor(monitor) && !self.windows.contains(&window_hwnd) {
-                        self.windows.push(window_hwnd);
-                        workspace.add_window(Window::new(&title, window_hwnd));
-                    }
+    /// Put the window reached by cycling through the history at its top
+    fn end_focus_cycle(&mut self) {
+        if let Some(cursor) = self.focus_history_cursor.take() {
+            if cursor < self.focus_history.len() {
+                let hwnd = self.focus_history.remove(cursor);
+                self.focus_history.insert(0, hwnd);
+            }
+        }
+    }
+
+    /// Get the position in the history of the next window that can be focused, after `start`
+    fn get_next_in_focus_history(&self, start: usize) -> Option<usize> {
+        let len = self.focus_history.len();
+
+        // The windows hidden in a scratchpad are skipped
+        (1..len).map(|offset| (start + offset) % len).find(|index| {
+            let hwnd = self.focus_history[*index];
+            self.workspaces
+                .iter()
+                .any(|workspace| workspace.contains_window(hwnd))
+        })
+    }
+
+    fn focus_last_window(&mut self) {
+        if let Some(index) = self.get_next_in_focus_history(0) {
+            self.focus_window(self.focus_history[index]);
+        }
+    }
+
+    fn cycle_focus_history(&mut self) {
+        let start = self.focus_history_cursor.unwrap_or(0);
+        if let Some(index) = self.get_next_in_focus_history(start) {
+            self.focus_history_cursor = Some(index);
+            self.focus_window(self.focus_history[index]);
+        }
+    }
+
+    /// Make the window the current one, showing its workspace if it is hidden
+    fn focus_window(&mut self, hwnd: isize) {
+        let Some(workspace_index) = self
+            .workspaces
+            .iter()
+            .position(|workspace| workspace.contains_window(hwnd))
+        else {
+            return;
+        };
+
+        if !self.workspaces[workspace_index].is_visible() {
+            self.focus_workspace(workspace_index);
+        }
+        self.current_workspace_index = workspace_index;
+        self.workspaces[workspace_index].set_current_window(hwnd);
+    }
+
+    fn get_current_window_hwnd(&self) -> Option<isize> {
+        self.workspaces
+            .get(self.current_workspace_index)
+            .and_then(|workspace| workspace.get_current_window())
+            .filter(|node| !node.value.minimized)
+            .map(|node| node.value.hwnd)
+            .filter(|hwnd| *hwnd != 0)
+    }
+
+    /// Put the current window of the WM in the foreground when it changed
+    fn focus_current_window(&mut self) {
+        let Some(hwnd) = self.get_current_window_hwnd() else {
+            return;
+        };
+
+        if self.focused_window != Some(hwnd) {
+            self.focused_window = Some(hwnd);
+            self.system.set_foreground_window(hwnd);
+            self.record_focus(hwnd);
+        }
+    }
+
+    fn set_window_title(&mut self, hwnd: isize, title: &str) {
+        for workspace in self.workspaces.iter_mut() {
+            if let Some(path) = workspace
+                .windows
+                .find_leaf_path(|window| window.hwnd == hwnd)
+            {
+                if let Some(node) = workspace.windows.get_mut(&path) {
+                    node.value.title = title.to_owned();
                 }
             }
         }
+        for scratchpad_window in self.scratchpad_windows.iter_mut() {
+            if scratchpad_window.window.hwnd == hwnd {
+                scratchpad_window.window.title = title.to_owned();
+            }
+        }
     }
 
     pub fn list_managable_windows(&self) {
-        let windows = self.get_windows();
+        let windows = self.system.get_windows();
         let windows = self.get_managed_windows(&windows);
 
         for window_hwnd in windows {
-            let title = Window::get_window_title(window_hwnd);
+            let title = self.system.get_window_title(window_hwnd);
 
             println!("{}", &title);
         }
     }
 
-    pub fn arrange_workspaces(&self) {
-        for workspace in self.workspaces.iter() {
+    pub fn arrange_workspaces(&mut self) {
+        for workspace in self.workspaces.iter_mut() {
             workspace.arrange_windows()
         }
     }
 
     pub fn handle_action(&mut self, action: WorkspaceAction) {
+        // Any other action ends the cycle through the focus history
+        if !matches!(action, WorkspaceAction::CycleFocusHistory) {
+            self.end_focus_cycle();
+        }
+
         match action {
             WorkspaceAction::NextAsCurrent => {
                 self.get_current_workspace().set_current_next();
@@ -142,16 +521,24 @@ impl WindowManager {
             WorkspaceAction::ToggleMode(mode) => {
                 self.get_current_workspace().set_current_tiling_mode(&mode);
             }
-            WorkspaceAction::PutCurrentWindowInWorkspace { workspace_index } => {
-                if self.get_current_workspace().windows.childrens.is_empty() {
-                    return;
+            WorkspaceAction::FocusWorkspace(target) => {
+                if let Some(workspace_index) = self.get_workspace_index(&target) {
+                    self.focus_workspace(workspace_index);
                 }
+            }
+            WorkspaceAction::PutCurrentWindowInWorkspace(target) => {
+                let Some(workspace_index) = self.get_workspace_index(&target) else {
+                    return;
+                };
 
                 let window = {
                     let workspace = self.get_current_workspace();
-                    let current_window = workspace.get_current_window().value.clone();
+                    let Some(current_window) = workspace.get_current_window() else {
+                        return;
+                    };
+                    let current_window = current_window.value.clone();
 
-                    Workspace::remove_window(&mut workspace.windows, current_window.hwnd);
+                    Workspace::<S>::remove_window(&mut workspace.windows, current_window.hwnd);
 
                     current_window
                 };
@@ -160,28 +547,745 @@ impl WindowManager {
                     new_workspace.add_window(window.clone());
                 }
             }
+            WorkspaceAction::MoveCurrentWorkspace(direction) => {
+                self.move_current_workspace(&direction);
+            }
+            WorkspaceAction::SwapWorkspaces(direction) => {
+                self.swap_workspaces(&direction);
+            }
+            WorkspaceAction::ToggleFloating => {
+                self.get_current_workspace().toggle_current_floating();
+            }
+            WorkspaceAction::ToggleSticky => {
+                self.get_current_workspace().toggle_current_sticky();
+            }
+            WorkspaceAction::SendCurrentToScratchpad { name } => {
+                self.send_current_to_scratchpad(&name);
+            }
+            WorkspaceAction::ToggleScratchpad { name } => {
+                self.toggle_scratchpad(&name);
+            }
+            WorkspaceAction::SetLayout(layout) => {
+                self.get_current_workspace().set_layout(layout);
+            }
+            WorkspaceAction::ChangeMasterRatio(delta) => {
+                self.get_current_workspace().change_master_ratio(delta);
+            }
+            WorkspaceAction::ChangeMasterCount(delta) => {
+                self.get_current_workspace().change_master_count(delta);
+            }
+            WorkspaceAction::SwapCurrentWithMaster => {
+                self.get_current_workspace().swap_current_with_master();
+            }
+            WorkspaceAction::ResizeCurrent { direction, delta } => {
+                self.get_current_workspace()
+                    .resize_current(&direction, delta);
+            }
+            WorkspaceAction::BalanceWeights => {
+                self.get_current_workspace().balance_weights();
+            }
+            WorkspaceAction::SetContainerMode(container_mode) => {
+                self.get_current_workspace()
+                    .set_current_container_mode(container_mode);
+            }
+            WorkspaceAction::SplitCurrent(direction) => {
+                self.get_current_workspace().split_current(direction);
+            }
+            WorkspaceAction::MoveCurrentIntoSibling { forward } => {
+                self.get_current_workspace()
+                    .move_current_into_sibling(forward);
+            }
+            WorkspaceAction::MoveCurrentOutOfContainer => {
+                self.get_current_workspace().move_current_out_of_container();
+            }
+            WorkspaceAction::FlattenCurrentContainer => {
+                self.get_current_workspace().flatten_current_container();
+            }
+            WorkspaceAction::FocusDirection(direction) => {
+                self.focus_direction(&direction);
+            }
+            WorkspaceAction::FocusLastWindow => self.focus_last_window(),
+            WorkspaceAction::CycleFocusHistory => self.cycle_focus_history(),
+            WorkspaceAction::SwapCurrentDirection(direction) => {
+                if !self
+                    .get_current_workspace()
+                    .swap_current_direction(&direction)
+                {
+                    self.move_current_to_adjacent_workspace(&direction);
+                }
+            }
+            WorkspaceAction::MoveCurrentDirection(direction) => {
+                if !self
+                    .get_current_workspace()
+                    .move_current_direction(&direction)
+                {
+                    self.move_current_to_adjacent_workspace(&direction);
+                }
+            }
+        }
+
+        self.focus_current_window();
+    }
+
+    fn get_workspace_index(&self, target: &WorkspaceTarget) -> Option<usize> {
+        match target {
+            WorkspaceTarget::Index(index) => {
+                Some(*index).filter(|index| *index < self.workspaces.len())
+            }
+            WorkspaceTarget::Name(name) => self
+                .workspaces
+                .iter()
+                .position(|workspace| workspace.get_name() == name),
+        }
+    }
+
+    /// Show the workspace in place of the one displayed on its monitor and make it the current one
+    fn focus_workspace(&mut self, workspace_index: usize) {
+        let Some(workspace) = self.workspaces.get(workspace_index) else {
+            return;
+        };
+        let monitor_handle = workspace.get_monitor().monitor_handle;
+
+        let mut sticky_windows = Vec::new();
+        for (index, workspace) in self.workspaces.iter_mut().enumerate() {
+            if index != workspace_index && workspace.is_on_monitor(monitor_handle) {
+                if workspace.is_visible() {
+                    sticky_windows.append(&mut workspace.take_sticky_windows());
+                }
+                workspace.hide();
+            }
+        }
+        self.workspaces[workspace_index].show();
+        self.workspaces[workspace_index].add_sticky_windows(sticky_windows);
+        self.current_workspace_index = workspace_index;
+    }
+
+    /// Move the current workspace onto the adjacent monitor, hiding the workspace shown there
+    ///
+    /// When no other workspace is left on the monitor, the workspace shown there takes its place
+    fn move_current_workspace(&mut self, direction: &Direction) {
+        let Some(adjacent_workspace_index) = self.get_adjacent_workspace_index(direction) else {
+            return;
+        };
+        let current_workspace_index = self.current_workspace_index;
+        let current_monitor = self.get_current_workspace().get_monitor().clone();
+        let adjacent_monitor = self.workspaces[adjacent_workspace_index]
+            .get_monitor()
+            .clone();
+
+        // Another workspace of the monitor left behind takes its place, if there is one
+        let replacing_workspace_index = self
+            .workspaces
+            .iter()
+            .enumerate()
+            .find(|(index, workspace)| {
+                *index != current_workspace_index
+                    && workspace.is_on_monitor(current_monitor.monitor_handle)
+            })
+            .map(|(index, _)| index)
+            .unwrap_or(adjacent_workspace_index);
+
+        // The sticky windows stay on their monitor
+        let current_sticky_windows = self.get_current_workspace().take_sticky_windows();
+        let adjacent_sticky_windows =
+            self.workspaces[adjacent_workspace_index].take_sticky_windows();
+
+        self.get_current_workspace().set_monitor(adjacent_monitor);
+        self.get_current_workspace()
+            .add_sticky_windows(adjacent_sticky_windows);
+
+        if replacing_workspace_index == adjacent_workspace_index {
+            self.workspaces[adjacent_workspace_index].set_monitor(current_monitor);
+        } else {
+            self.workspaces[adjacent_workspace_index].hide();
+            self.workspaces[replacing_workspace_index].show();
+        }
+        self.workspaces[replacing_workspace_index].add_sticky_windows(current_sticky_windows);
+    }
+
+    /// Exchange the monitors of the current workspace and of the one shown on the adjacent monitor
+    fn swap_workspaces(&mut self, direction: &Direction) {
+        let Some(adjacent_workspace_index) = self.get_adjacent_workspace_index(direction) else {
+            return;
+        };
+        let current_monitor = self.get_current_workspace().get_monitor().clone();
+        let adjacent_monitor = self.workspaces[adjacent_workspace_index]
+            .get_monitor()
+            .clone();
+
+        // The sticky windows stay on their monitor
+        let current_sticky_windows = self.get_current_workspace().take_sticky_windows();
+        let adjacent_sticky_windows =
+            self.workspaces[adjacent_workspace_index].take_sticky_windows();
+
+        self.workspaces[adjacent_workspace_index].set_monitor(current_monitor);
+        self.workspaces[adjacent_workspace_index].add_sticky_windows(current_sticky_windows);
+        self.get_current_workspace().set_monitor(adjacent_monitor);
+        self.get_current_workspace()
+            .add_sticky_windows(adjacent_sticky_windows);
+    }
+
+    fn send_current_to_scratchpad(&mut self, name: &str) {
+        let workspace = self.get_current_workspace();
+        let Some(current_window) = workspace.get_current_window() else {
+            return;
+        };
+        let window = current_window.value.clone();
+        Workspace::<S>::remove_window(&mut workspace.windows, window.hwnd);
+
+        self.hide_in_scratchpad(name, window);
+    }
+
+    /// Park the window outside of the monitors and keep it in the scratchpad
+    fn hide_in_scratchpad(&mut self, name: &str, mut window: Window) {
+        if let Some(rect) = self.system.get_window_rect(window.hwnd) {
+            // Remember where the window was, to give it back when the WM quits
+            if !is_parked_rect(&rect) {
+                window.floating_rect = Some(rect);
+            }
+
+            let parked_rect = get_parked_rect(&rect);
+            self.system.set_window_pos(
+                window.hwnd,
+                parked_rect.left,
+                parked_rect.top,
+                parked_rect.width(),
+                parked_rect.height(),
+            );
+        }
+
+        self.scratchpad_windows
+            .retain(|scratchpad_window| scratchpad_window.window.hwnd != window.hwnd);
+        self.scratchpad_windows
+            .push(ScratchpadWindow::new(name, window));
+    }
+
+    /// Show the windows of the scratchpad over the current monitor, or hide them if they already are
+    fn toggle_scratchpad(&mut self, name: &str) {
+        let hwnds: Vec<isize> = self
+            .scratchpad_windows
+            .iter()
+            .filter(|scratchpad_window| scratchpad_window.name == name)
+            .map(|scratchpad_window| scratchpad_window.window.hwnd)
+            .collect();
+
+        // Hide the windows wherever they are shown, they come back on the current monitor otherwise
+        let mut shown_on_current_workspace = false;
+        for hwnd in hwnds.iter().copied() {
+            for (index, workspace) in self.workspaces.iter_mut().enumerate() {
+                let Some(path) = workspace
+                    .windows
+                    .find_leaf_path(|window| window.hwnd == hwnd)
+                else {
+                    continue;
+                };
+                let window = workspace.windows.get(&path).unwrap().value.clone();
+                workspace.forget_window(hwnd);
+
+                shown_on_current_workspace |= index == self.current_workspace_index;
+                self.hide_in_scratchpad(name, window);
+                break;
+            }
+        }
+
+        if shown_on_current_workspace {
+            return;
         }
+
+        let windows: Vec<Window> = self
+            .scratchpad_windows
+            .iter()
+            .filter(|scratchpad_window| scratchpad_window.name == name)
+            .map(|scratchpad_window| scratchpad_window.window.clone())
+            .collect();
+        for window in windows {
+            self.get_current_workspace().add_centered_window(window);
+        }
+    }
+
+    /// Focus the nearest window in the direction, continuing on the adjacent monitor at the edge
+    fn focus_direction(&mut self, direction: &Direction) {
+        let workspace = self.get_current_workspace();
+        if workspace.focus_direction(direction) {
+            return;
+        }
+        let current_rect = workspace
+            .get_current_rect()
+            .unwrap_or(workspace.get_monitor().work_area);
+
+        let Some(adjacent_workspace_index) = self.get_adjacent_workspace_index(direction) else {
+            return;
+        };
+        self.current_workspace_index = adjacent_workspace_index;
+        self.get_current_workspace()
+            .focus_nearest(&current_rect, direction);
+    }
+
+    /// Move the current window to the workspace on the adjacent monitor, next to the window it reaches
+    fn move_current_to_adjacent_workspace(&mut self, direction: &Direction) {
+        let Some(adjacent_workspace_index) = self.get_adjacent_workspace_index(direction) else {
+            return;
+        };
+
+        let workspace = self.get_current_workspace();
+        let Some(current_rect) = workspace.get_current_rect() else {
+            return;
+        };
+        let Some(current_window) = workspace.get_current_window() else {
+            return;
+        };
+        let window = current_window.value.clone();
+        Workspace::<S>::remove_window(&mut workspace.windows, window.hwnd);
+
+        self.current_workspace_index = adjacent_workspace_index;
+        let workspace = self.get_current_workspace();
+        workspace.focus_nearest(&current_rect, direction);
+        workspace.add_window(window);
+    }
+
+    /// Get the index of the workspace on the nearest monitor in the direction
+    fn get_adjacent_workspace_index(&self, direction: &Direction) -> Option<usize> {
+        let monitors_rects: Vec<(isize, Rect)> = self
+            .workspaces
+            .iter()
+            .enumerate()
+            .filter(|(_, workspace)| workspace.is_visible())
+            .map(|(index, workspace)| (index as isize, workspace.get_monitor().rect))
+            .collect();
+        let current_rect = self
+            .workspaces
+            .get(self.current_workspace_index)?
+            .get_monitor()
+            .rect;
+
+        get_nearest_in_direction(&monitors_rects, &current_rect, direction)
+            .map(|index| index as usize)
     }
 
+    /// Make the workspace under the cursor the current one when the cursor enters its monitor
     pub fn update_current_monitor(&mut self, x: i32, y: i32) {
-        for (index, workspace) in self.workspaces.iter().enumerate() {
-            if workspace.is_current_workspace(x, y) {
+        let hovered_workspace_index = self
+            .workspaces
+            .iter()
+            .position(|workspace| workspace.is_current_workspace(x, y));
+        let hovered_monitor_handle = hovered_workspace_index
+            .map(|index| self.workspaces[index].get_monitor().monitor_handle);
+
+        if hovered_monitor_handle != self.hovered_monitor_handle {
+            if let Some(index) = hovered_workspace_index {
                 self.current_workspace_index = index;
             }
+            self.hovered_monitor_handle = hovered_monitor_handle;
         }
     }
 
-    fn get_current_workspace(&mut self) -> &mut Workspace {
+    fn get_current_workspace(&mut self) -> &mut Workspace<S> {
         &mut self.workspaces[self.current_workspace_index]
     }
 }
 
-unsafe extern "system" fn get_window_def(hwnd: isize, data: LPARAM) -> i32 {
-    if IsWindowVisible(hwnd) == 0 {
-        return 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigBuilder;
+    use crate::platform::fake::FakeWindowSystem;
+    use crate::scratchpad::DEFAULT_SCRATCHPAD;
+
+    fn create_config(test_name: &str, content: &str) -> Arc<Mutex<Config>> {
+        let config_path = std::env::temp_dir().join(format!("wwm-{}", test_name));
+        std::fs::write(&config_path, content).expect("Could not write the test configuration !");
+
+        Arc::new(Mutex::new(
+            ConfigBuilder::new(config_path.to_str().unwrap()).build(),
+        ))
+    }
+
+    fn create_window_manager(
+        test_name: &str,
+    ) -> (
+        Arc<FakeWindowSystem>,
+        WindowManager<FakeWindowSystem>,
+        isize,
+    ) {
+        let config = create_config(test_name, "workspace 1 main\nrule managed .*\n");
+
+        let system = Arc::new(FakeWindowSystem::new());
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        let mut window_manager = WindowManager::new(config, Arc::clone(&system));
+        window_manager.get_monitors();
+
+        (system, window_manager, monitor)
     }
 
-    let windows = &mut *(data as *mut Vec<isize>);
-    windows.push(hwnd);
-    1
+    /// Create a window manager with a workspace on a left and a right monitor
+    fn create_dual_monitor_window_manager(
+        test_name: &str,
+    ) -> (
+        Arc<FakeWindowSystem>,
+        WindowManager<FakeWindowSystem>,
+        isize,
+        isize,
+    ) {
+        let config = create_config(
+            test_name,
+            "workspace 1 left\nworkspace 2 right\nrule managed .*\n",
+        );
+
+        let system = Arc::new(FakeWindowSystem::new());
+        let left_monitor = system.add_monitor("left", Rect::new(0, 0, 1000, 600));
+        let right_monitor = system.add_monitor("right", Rect::new(1000, 0, 2000, 600));
+        let mut window_manager = WindowManager::new(config, Arc::clone(&system));
+        window_manager.get_monitors();
+
+        (system, window_manager, left_monitor, right_monitor)
+    }
+
+    fn get_current_window(window_manager: &WindowManager<FakeWindowSystem>) -> Option<isize> {
+        window_manager.workspaces[window_manager.current_workspace_index]
+            .get_current_window()
+            .map(|node| node.value.hwnd)
+    }
+
+    fn get_tiled_windows(window_manager: &WindowManager<FakeWindowSystem>) -> Vec<isize> {
+        let windows = &window_manager.workspaces[0].windows;
+        windows
+            .leaf_paths()
+            .iter()
+            .filter_map(|path| windows.get(path))
+            .map(|node| node.value.hwnd)
+            .collect()
+    }
+
+    #[test]
+    fn fetch_removes_a_window_closed_while_another_opens() {
+        let (system, mut window_manager, monitor) = create_window_manager("open-close");
+        let kept = system.open_window("kept", monitor);
+        let closed = system.open_window("closed", monitor);
+        window_manager.fetch_windows();
+
+        system.close_window(closed);
+        let opened = system.open_window("opened", monitor);
+        window_manager.fetch_windows();
+
+        let mut tiled_windows = get_tiled_windows(&window_manager);
+        tiled_windows.sort();
+        assert_eq!(tiled_windows, vec![kept, opened]);
+        assert!(!window_manager.windows.contains(&closed));
+    }
+
+    #[test]
+    fn fetch_replaces_every_window_closed_while_as_many_open() {
+        let (system, mut window_manager, monitor) = create_window_manager("replace-all");
+        let first = system.open_window("first", monitor);
+        let second = system.open_window("second", monitor);
+        window_manager.fetch_windows();
+
+        system.close_window(first);
+        system.close_window(second);
+        let third = system.open_window("third", monitor);
+        let fourth = system.open_window("fourth", monitor);
+        window_manager.fetch_windows();
+        window_manager.arrange_workspaces();
+
+        let mut tiled_windows = get_tiled_windows(&window_manager);
+        tiled_windows.sort();
+        assert_eq!(tiled_windows, vec![third, fourth]);
+        assert_eq!(
+            system.get_window(third).unwrap().rect.width()
+                + system.get_window(fourth).unwrap().rect.width(),
+            1920
+        );
+    }
+
+    #[test]
+    fn focus_crossing_monitors_can_land_on_the_current_window() {
+        let (system, mut window_manager, left_monitor, right_monitor) =
+            create_dual_monitor_window_manager("cross-monitors");
+        let right_half = system.open_window("right half", left_monitor);
+        let left_half = system.open_window("left half", left_monitor);
+        let other_monitor = system.open_window("other monitor", right_monitor);
+        window_manager.fetch_windows();
+        window_manager.arrange_workspaces();
+        assert_eq!(
+            system.get_window(left_half).unwrap().rect,
+            Rect::new(0, 0, 500, 600)
+        );
+
+        window_manager.current_workspace_index = 0;
+        window_manager.workspaces[0].set_current_window(right_half);
+        window_manager.focus_direction(&Direction::Right);
+        assert_eq!(get_current_window(&window_manager), Some(other_monitor));
+
+        window_manager.focus_direction(&Direction::Left);
+        assert_eq!(get_current_window(&window_manager), Some(right_half));
+    }
+
+    #[test]
+    fn restore_brings_back_the_hidden_and_scratchpad_windows() {
+        let config = create_config(
+            "restore",
+            "workspace 1 main\nworkspace 2 main\nrule managed .*\n",
+        );
+        let system = Arc::new(FakeWindowSystem::new());
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        let mut window_manager = WindowManager::new(config, Arc::clone(&system));
+        window_manager.get_monitors();
+
+        let scratchpad = system.open_window("scratchpad", monitor);
+        window_manager.fetch_windows();
+        window_manager.arrange_workspaces();
+        window_manager.send_current_to_scratchpad("default");
+        let hidden = system.open_window("hidden", monitor);
+        window_manager.workspaces[1].add_window(Window::new("hidden", hidden));
+        assert!(is_parked_rect(&system.get_window(scratchpad).unwrap().rect));
+        assert!(is_parked_rect(&system.get_window(hidden).unwrap().rect));
+
+        window_manager.restore_all();
+
+        assert_eq!(
+            system.get_window(scratchpad).unwrap().rect,
+            Rect::new(0, 0, 1920, 1080)
+        );
+        assert_eq!(
+            system.get_window(hidden).unwrap().rect,
+            Rect::new(0, 0, 1920, 1080)
+        );
+    }
+
+    #[test]
+    fn reload_keeps_the_windows_scratchpads_and_floating_modes() {
+        let (system, mut window_manager, monitor) = create_window_manager("reload");
+        let tiled = system.open_window("tiled", monitor);
+        let floating = system.open_window("floating", monitor);
+        let scratchpad = system.open_window("scratchpad", monitor);
+        window_manager.fetch_windows();
+        window_manager.send_current_to_scratchpad("default");
+        window_manager.workspaces[0].set_current_window(floating);
+        window_manager.workspaces[0].toggle_current_floating();
+
+        window_manager.config = create_config(
+            "reload",
+            "workspace 1 main\nworkspace 2 main\nrule managed .*\n",
+        );
+        window_manager.get_monitors();
+        window_manager.fetch_windows();
+
+        assert_eq!(window_manager.workspaces.len(), 2);
+        assert_eq!(get_tiled_windows(&window_manager), vec![floating, tiled]);
+        assert_eq!(
+            window_manager.workspaces[0]
+                .get_current_window()
+                .map(|node| node.value.mode.clone()),
+            Some(TilingMode::Floating)
+        );
+        assert_eq!(window_manager.scratchpad_windows.len(), 1);
+        assert_eq!(window_manager.scratchpad_windows[0].window.hwnd, scratchpad);
+    }
+
+    #[test]
+    fn moving_the_only_workspace_of_a_monitor_leaves_its_sticky_windows() {
+        let (system, mut window_manager, left_monitor, right_monitor) =
+            create_dual_monitor_window_manager("move-sticky");
+        let sticky = system.open_window("sticky", left_monitor);
+        let moved = system.open_window("moved", left_monitor);
+        let displaced = system.open_window("displaced", right_monitor);
+        window_manager.fetch_windows();
+
+        window_manager.current_workspace_index = 0;
+        window_manager.workspaces[0].set_current_window(sticky);
+        window_manager.workspaces[0].toggle_current_sticky();
+        window_manager.workspaces[0].set_current_window(moved);
+        window_manager.move_current_workspace(&Direction::Right);
+        window_manager.arrange_workspaces();
+
+        let left_workspace = window_manager
+            .workspaces
+            .iter()
+            .find(|workspace| workspace.is_on_monitor(left_monitor))
+            .unwrap();
+        assert!(left_workspace.is_visible());
+        assert!(left_workspace.contains_window(sticky));
+        assert!(left_workspace.contains_window(displaced));
+        assert!(window_manager.workspaces[0].is_on_monitor(right_monitor));
+        assert!(!window_manager.workspaces[0].contains_window(sticky));
+        assert_eq!(
+            system.get_window(moved).unwrap().rect,
+            Rect::new(1000, 0, 2000, 600)
+        );
+    }
+
+    #[test]
+    fn placement_rules_manage_the_windows_unless_excluded() {
+        let config = create_config(
+            "placement-rules",
+            "workspace 1 main\nrule scratchpad title=^term$\nrule exclude,float title=^excluded$\n",
+        );
+        let system = Arc::new(FakeWindowSystem::new());
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        let mut window_manager = WindowManager::new(config, Arc::clone(&system));
+        window_manager.get_monitors();
+
+        let term = system.open_window("term", monitor);
+        let excluded = system.open_window("excluded", monitor);
+        let other = system.open_window("other", monitor);
+        window_manager.fetch_windows();
+
+        assert_eq!(window_manager.scratchpad_windows.len(), 1);
+        assert_eq!(window_manager.scratchpad_windows[0].window.hwnd, term);
+        window_manager.toggle_scratchpad(DEFAULT_SCRATCHPAD);
+        assert!(!is_parked_rect(&system.get_window(term).unwrap().rect));
+        assert!(!window_manager.windows.contains(&excluded));
+        assert!(!window_manager.windows.contains(&other));
+    }
+
+    #[test]
+    fn fetch_catches_up_with_the_missed_events_only() {
+        let (system, mut window_manager, monitor) = create_window_manager("missed-events");
+        let followed = system.open_window("followed", monitor);
+        for event in system.take_events() {
+            window_manager.handle_window_event(event);
+        }
+        let missed = system.open_window("missed", monitor);
+        system.take_events();
+
+        window_manager.fetch_windows();
+
+        let mut tiled_windows = get_tiled_windows(&window_manager);
+        tiled_windows.sort();
+        assert_eq!(tiled_windows, vec![followed, missed]);
+    }
+
+    #[test]
+    fn closing_the_current_window_focuses_the_next_one() {
+        let (system, mut window_manager, monitor) = create_window_manager("close-current");
+        let kept = system.open_window("kept", monitor);
+        let closed = system.open_window("closed", monitor);
+        window_manager.fetch_windows();
+        window_manager.workspaces[0].set_current_window(closed);
+        system.set_foreground_window(closed);
+        for event in system.take_events() {
+            window_manager.handle_window_event(event);
+        }
+
+        system.close_window(closed);
+        for event in system.take_events() {
+            window_manager.handle_window_event(event);
+        }
+
+        assert_eq!(system.get_foreground_window(), Some(kept));
+    }
+
+    #[test]
+    fn closing_another_window_keeps_the_current_one() {
+        let (system, mut window_manager, monitor) = create_window_manager("close-other");
+        system.open_window("first", monitor);
+        let current = system.open_window("current", monitor);
+        let closed = system.open_window("closed", monitor);
+        window_manager.fetch_windows();
+        window_manager.workspaces[0].set_current_window(current);
+        system.set_foreground_window(current);
+        for event in system.take_events() {
+            window_manager.handle_window_event(event);
+        }
+
+        system.close_window(closed);
+        for event in system.take_events() {
+            window_manager.handle_window_event(event);
+        }
+
+        assert_eq!(window_manager.get_current_window_hwnd(), Some(current));
+        assert_eq!(system.get_foreground_window(), Some(current));
+    }
+
+    #[test]
+    fn floating_windows_sent_to_a_hidden_workspace_come_back_with_it() {
+        let config = create_config(
+            "hidden-floating",
+            "workspace 1 main\nworkspace 2 main name=two\nrule managed .*\nrule float,workspace=two title=^ruled$\n",
+        );
+        let system = Arc::new(FakeWindowSystem::new());
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        let mut window_manager = WindowManager::new(config, Arc::clone(&system));
+        window_manager.get_monitors();
+
+        let ruled = system.open_window("ruled", monitor);
+        window_manager.fetch_windows();
+        let toggled = system.open_window("toggled", monitor);
+        window_manager.fetch_windows();
+        window_manager.workspaces[0].set_current_window(toggled);
+        window_manager.handle_action(WorkspaceAction::ToggleFloating);
+        window_manager.handle_action(WorkspaceAction::PutCurrentWindowInWorkspace(
+            WorkspaceTarget::Index(1),
+        ));
+        assert!(is_parked_rect(&system.get_window(ruled).unwrap().rect));
+        assert!(is_parked_rect(&system.get_window(toggled).unwrap().rect));
+
+        window_manager.handle_action(WorkspaceAction::FocusWorkspace(WorkspaceTarget::Index(1)));
+        window_manager.arrange_workspaces();
+
+        assert!(!is_parked_rect(&system.get_window(ruled).unwrap().rect));
+        assert!(!is_parked_rect(&system.get_window(toggled).unwrap().rect));
+    }
+
+    #[test]
+    fn restore_brings_back_the_parked_columns_with_gaps() {
+        let config = create_config(
+            "restore-gaps",
+            "workspace 1 main\nlayout scrolling\ngaps inner 8\nrule managed .*\n",
+        );
+        let system = Arc::new(FakeWindowSystem::new());
+        let monitor = system.add_monitor("main", Rect::new(0, 0, 1920, 1080));
+        let mut window_manager = WindowManager::new(config, Arc::clone(&system));
+        window_manager.get_monitors();
+
+        let windows: Vec<isize> = (0..3)
+            .map(|index| system.open_window(&format!("column {}", index), monitor))
+            .collect();
+        window_manager.fetch_windows();
+        window_manager.arrange_workspaces();
+        assert!(windows
+            .iter()
+            .any(|hwnd| is_parked_rect(&system.get_window(*hwnd).unwrap().rect)));
+
+        window_manager.restore_all();
+
+        for hwnd in windows {
+            let rect = system.get_window(hwnd).unwrap().rect;
+            assert!(rect.left >= 0 && rect.top >= 0, "{:?} is off-screen", rect);
+        }
+    }
+
+    #[test]
+    fn minimised_windows_keep_their_place_out_of_the_layout() {
+        let (system, mut window_manager, monitor) = create_window_manager("minimise");
+        let first = system.open_window("first", monitor);
+        let second = system.open_window("second", monitor);
+        window_manager.fetch_windows();
+        window_manager.arrange_workspaces();
+        let tiled_windows = get_tiled_windows(&window_manager);
+        let second_rect = system.get_window(second).unwrap().rect;
+
+        system.set_visible(second, false);
+        for event in system.take_events() {
+            window_manager.handle_window_event(event);
+        }
+        window_manager.arrange_workspaces();
+        assert_eq!(
+            system.get_window(first).unwrap().rect,
+            Rect::new(0, 0, 1920, 1080)
+        );
+        assert_eq!(get_tiled_windows(&window_manager), tiled_windows);
+
+        // The periodic fetch does not take the minimised window for a closed one
+        window_manager.fetch_windows();
+        assert!(window_manager.windows.contains(&second));
+
+        system.set_visible(second, true);
+        for event in system.take_events() {
+            window_manager.handle_window_event(event);
+        }
+        window_manager.arrange_workspaces();
+        assert_eq!(get_tiled_windows(&window_manager), tiled_windows);
+        assert_eq!(system.get_window(second).unwrap().rect, second_rect);
+    }
 }
@@ -1,208 +1,1101 @@
+use std::sync::Arc;
+
 use crate::{
-    monitor::Monitor,
-    tree::{Node, TilingDirection},
-    windows::{TilingMode, Window},
+    layout::{
+        compute_layout, get_parked_rect, is_parked_rect, Layout, LayoutApplier, LayoutOptions,
+        LayoutPlan, MAX_MASTER_RATIO, MIN_MASTER_RATIO,
+    },
+    monitor::{get_nearest_in_direction, Direction, Monitor, Rect},
+    platform::WindowSystem,
+    tree::{ContainerMode, Node, TilingDirection},
+    windows::{FloatingPlacement, InsertPosition, TilingMode, Window},
 };
 
 type WindowType = Box<Node<Window>>;
 
-pub struct Workspace {
+const MIN_WEIGHT: f32 = 0.1;
+/// Offset between two windows placed by the cascade policy
+const CASCADE_STEP: i32 = 32;
+
+pub struct Workspace<S: WindowSystem> {
+    name: String,
+    system: Arc<S>,
     monitor: Monitor,
     pub windows: WindowType,
+    layout_options: LayoutOptions,
+    layout_applier: LayoutApplier,
+    scroll_offset: i32,
+    /// Whether the windows are on the monitor, the hidden workspaces park them outside of it
+    visible: bool,
 
     current_window_index: usize,
 }
-impl Workspace {
-    pub fn new(monitor: Monitor) -> Self {
+impl<S: WindowSystem> Workspace<S> {
+    pub fn new(
+        name: &str,
+        monitor: Monitor,
+        system: Arc<S>,
+        layout_options: LayoutOptions,
+    ) -> Self {
         Self {
+            name: name.to_owned(),
+            system,
             monitor,
-            windows: Box::new(Node::new(Window::new("()", 1), TilingDirection::Vertical)),
+            windows: Box::new(Node::new(Window::container(), TilingDirection::Vertical)),
+            layout_options,
+            layout_applier: LayoutApplier::new(),
+            scroll_offset: 0,
+            visible: true,
             current_window_index: 0,
         }
     }
 
     pub fn add_window(&mut self, window: Window) {
-        self.windows.insert(
-            window,
-            self.current_window_index,
-            TilingDirection::Horizontal,
+        self.add_window_at(window, InsertPosition::default());
+    }
+
+    /// Add the window next to the current, first or last leaf
+    ///
+    /// The binary space partition layouts only use the position to pick the leaf they split
+    pub fn add_window_at(&mut self, window: Window, position: InsertPosition) {
+        let hwnd = window.hwnd;
+
+        let mut paths = self.windows.leaf_paths();
+        let target_path = match position {
+            InsertPosition::BeforeCurrent | InsertPosition::AfterCurrent => self.get_current_path(),
+            InsertPosition::First if !paths.is_empty() => Some(paths.swap_remove(0)),
+            InsertPosition::First => None,
+            InsertPosition::Last => paths.pop(),
+        };
+        let insert_after = matches!(
+            position,
+            InsertPosition::AfterCurrent | InsertPosition::Last
         );
+
+        match target_path {
+            Some(target_path) if self.layout_options.layout.is_binary_space_partition() => {
+                self.split_window(&target_path, window)
+            }
+            Some(target_path) => {
+                let (index, parent_path) = target_path.split_last().unwrap();
+                if let Some(parent) = self.windows.get_mut(parent_path) {
+                    // The new window follows the direction of its siblings
+                    let direction = parent.childrens[*index].direction.clone();
+                    parent.insert(window, *index + insert_after as usize, direction);
+                }
+            }
+            None => self.windows.insert(window, 0, TilingDirection::Horizontal),
+        }
+
+        if !self.visible {
+            self.park_window(hwnd);
+        }
+        self.set_current_window(hwnd);
+    }
+
+    /// Replace the window at the path with a container holding it and the new window
+    fn split_window(&mut self, path: &[usize], window: Window) {
+        let current_hwnd = match self.windows.get(path) {
+            Some(node) => node.value.hwnd,
+            None => return,
+        };
+        let current_rect = self
+            .compute_layout()
+            .into_iter()
+            .find(|(hwnd, _)| *hwnd == current_hwnd)
+            .map(|(_, rect)| rect)
+            .unwrap_or(self.monitor.work_area);
+
+        // The spiral turns right, down, left then up as the windows get nested
+        let depth = path.len() - 1;
+        let (split_direction, insert_before) = match self.layout_options.layout {
+            Layout::Spiral => {
+                let direction = if depth.is_multiple_of(2) {
+                    TilingDirection::Horizontal
+                } else {
+                    TilingDirection::Vertical
+                };
+                (direction, (depth / 2) % 2 == 1)
+            }
+            _ => {
+                let direction = if current_rect.width() >= current_rect.height() {
+                    TilingDirection::Horizontal
+                } else {
+                    TilingDirection::Vertical
+                };
+                (direction, false)
+            }
+        };
+
+        if let Some(container) = Self::wrap_in_container(&mut self.windows, path, split_direction) {
+            let direction = container.childrens[0].direction.clone();
+            container.insert(window, if insert_before { 0 } else { 1 }, direction);
+        }
+    }
+
+    /// Replace the node at the path with a container holding it, splitting in the given direction
+    fn wrap_in_container<'a>(
+        windows: &'a mut WindowType,
+        path: &[usize],
+        direction: TilingDirection,
+    ) -> Option<&'a mut Node<Window>> {
+        let current_node = windows.get_mut(path)?;
+
+        let mut container = Node::new(Window::container(), current_node.direction.clone());
+        container.weight = current_node.weight;
+        let mut previous_node = std::mem::replace(current_node, container);
+        previous_node.direction = direction;
+        previous_node.weight = 1.0;
+        current_node.childrens.push(Box::new(previous_node));
+
+        Some(current_node)
     }
 
-    pub fn remove_window(window: &mut WindowType, window_handle: isize) {
+    /// Remove the window from the tree, returning whether it was found
+    pub fn remove_window(window: &mut WindowType, window_handle: isize) -> bool {
         // Find the window to remove
         let has_window_to_remove = window
             .childrens
             .iter()
-            .any(|child| child.value.hwnd == window_handle);
+            .any(|child| child.is_leaf() && child.value.hwnd == window_handle);
 
         if !has_window_to_remove {
-            for children in window.childrens.iter_mut() {
-                Workspace::remove_window(children, window_handle);
+            for index in 0..window.childrens.len() {
+                if Self::remove_window(&mut window.childrens[index], window_handle) {
+                    Self::collapse_container(window, index);
+                    return true;
+                }
             }
-            return;
+            return false;
         }
 
         // Get the parent and retain only the windows that do not have the handle passed in parameter
         window
             .childrens
-            .retain(|child| child.value.hwnd != window_handle);
+            .retain(|child| !child.is_leaf() || child.value.hwnd != window_handle);
+        true
+    }
+
+    /// Remove the window from the workspace, the current window stays current unless it is the one removed
+    ///
+    /// Returns whether the workspace held the window
+    pub fn forget_window(&mut self, hwnd: isize) -> bool {
+        let current_hwnd = self.get_current_window().map(|node| node.value.hwnd);
+        if !Self::remove_window(&mut self.windows, hwnd) {
+            return false;
+        }
+
+        match current_hwnd {
+            Some(current_hwnd) if current_hwnd != hwnd => self.set_current_window(current_hwnd),
+            _ => self.activate_current_path(),
+        }
+        true
+    }
+
+    /// Drop the container at the index if it is empty, or replace it with its node if it holds only one
+    fn collapse_container(window: &mut Node<Window>, index: usize) {
+        let container = &mut window.childrens[index];
+        match container.childrens.len() {
+            0 => {
+                window.childrens.remove(index);
+            }
+            1 => {
+                let mut only_children = container.childrens.pop().unwrap();
+                only_children.direction = container.direction.clone();
+                only_children.weight = container.weight;
+                *container = only_children;
+            }
+            _ => {}
+        }
+    }
+
+    /// Wrap the current window in a container, so the next window opens next to it in the given direction
+    pub fn split_current(&mut self, direction: TilingDirection) {
+        if let Some(current_path) = self.get_current_path() {
+            Self::wrap_in_container(&mut self.windows, &current_path, direction);
+        }
+    }
+
+    /// Move the current window inside the next or previous sibling container
+    pub fn move_current_into_sibling(&mut self, forward: bool) {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
+        let (index, parent_path) = current_path.split_last().unwrap();
+        let Some(parent) = self.windows.get_mut(parent_path) else {
+            return;
+        };
+
+        let sibling_index = if forward {
+            index + 1
+        } else {
+            match index.checked_sub(1) {
+                Some(sibling_index) => sibling_index,
+                None => return,
+            }
+        };
+        let direction = match parent.childrens.get(sibling_index) {
+            Some(sibling) if !sibling.is_leaf() => sibling.childrens[0].direction.clone(),
+            _ => return,
+        };
+
+        let mut node = parent.childrens.remove(*index);
+        let hwnd = node.value.hwnd;
+        node.direction = direction;
+        node.weight = 1.0;
+        if forward {
+            parent.childrens[*index].childrens.insert(0, node);
+        } else {
+            parent.childrens[sibling_index].childrens.push(node);
+        }
+
+        self.set_current_window(hwnd);
+    }
+
+    /// Move the current window out of its container, right after it in the parent container
+    pub fn move_current_out_of_container(&mut self) {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
+        if current_path.len() < 2 {
+            return;
+        }
+
+        let (index, parent_path) = current_path.split_last().unwrap();
+        let (parent_index, grandparent_path) = parent_path.split_last().unwrap();
+        let Some(grandparent) = self.windows.get_mut(grandparent_path) else {
+            return;
+        };
+
+        let container = &mut grandparent.childrens[*parent_index];
+        let direction = container.direction.clone();
+        let mut node = container.childrens.remove(*index);
+        let hwnd = node.value.hwnd;
+        node.direction = direction;
+        node.weight = 1.0;
+
+        grandparent.childrens.insert(parent_index + 1, node);
+        Self::collapse_container(grandparent, *parent_index);
+
+        self.set_current_window(hwnd);
+    }
+
+    /// Replace the container holding the current window with its childrens
+    pub fn flatten_current_container(&mut self) {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
+        if current_path.len() < 2 {
+            return;
+        }
+
+        let parent_path = &current_path[..current_path.len() - 1];
+        let (parent_index, grandparent_path) = parent_path.split_last().unwrap();
+        let Some(grandparent) = self.windows.get_mut(grandparent_path) else {
+            return;
+        };
+
+        let container = grandparent.childrens.remove(*parent_index);
+        for (offset, mut children) in container.childrens.into_iter().enumerate() {
+            children.direction = container.direction.clone();
+            children.weight = 1.0;
+            grandparent
+                .childrens
+                .insert(parent_index + offset, children);
+        }
+    }
+
+    fn get_current_path(&self) -> Option<Vec<usize>> {
+        let mut paths = self.windows.leaf_paths();
+        if paths.is_empty() {
+            return None;
+        }
+
+        let index = self.current_window_index.min(paths.len() - 1);
+        Some(paths.swap_remove(index))
+    }
+
+    pub fn get_current_window(&self) -> Option<&Node<Window>> {
+        self.windows.get(&self.get_current_path()?)
     }
 
-    pub fn get_current_window(&self) -> &WindowType {
-        &self.windows.childrens[self.current_window_index]
+    pub fn set_current_window(&mut self, hwnd: isize) {
+        if let Some(index) = self
+            .windows
+            .leaf_paths()
+            .iter()
+            .position(|path| self.windows.get(path).map(|node| node.value.hwnd) == Some(hwnd))
+        {
+            self.current_window_index = index;
+            self.activate_current_path();
+        }
+    }
+
+    /// Leave the minimised window out of the layout, keeping its place in the tree, or give it back
+    ///
+    /// Returns whether the workspace holds the window
+    pub fn set_window_minimized(&mut self, hwnd: isize, minimized: bool) -> bool {
+        let Some(path) = self.windows.find_leaf_path(|window| window.hwnd == hwnd) else {
+            return false;
+        };
+        let is_current = self.get_current_path().as_ref() == Some(&path);
+        if let Some(node) = self.windows.get_mut(&path) {
+            node.value.minimized = minimized;
+        }
+
+        if minimized && is_current {
+            // The next window that is not minimised becomes the current one
+            self.set_current_next();
+        } else if !minimized && !self.visible {
+            self.park_window(hwnd);
+        }
+        true
+    }
+
+    fn is_current_minimized(&self) -> bool {
+        self.get_current_window()
+            .is_some_and(|node| node.value.minimized)
+    }
+
+    /// Show the current window in the tabbed and stacked containers holding it
+    fn activate_current_path(&mut self) {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
+
+        for depth in 0..current_path.len() {
+            if let Some(node) = self.windows.get_mut(&current_path[..depth]) {
+                node.active_children = current_path[depth];
+            }
+        }
+    }
+
+    /// Change how the container holding the current window shows its childrens
+    pub fn set_current_container_mode(&mut self, container_mode: ContainerMode) {
+        let parent_path = match self.get_current_path() {
+            Some(current_path) => current_path[..current_path.len() - 1].to_vec(),
+            None => Vec::new(),
+        };
+
+        if let Some(container) = self.windows.get_mut(&parent_path) {
+            container.container_mode = container_mode;
+        }
+        self.activate_current_path();
     }
 
-    pub fn arrange_windows(&self) {
-        self.arrange_recursive(
+    pub fn compute_layout(&self) -> LayoutPlan {
+        compute_layout(
             &self.windows,
-            self.monitor.rect.left,
-            self.monitor.rect.top,
-            self.monitor.width,
-            self.monitor.height,
+            &self.monitor.work_area,
+            &self.layout_options,
+            self.scroll_offset,
         )
     }
 
+    /// Scroll the strip of the scrolling layout as little as possible to show the current column
+    fn scroll_to_current(&mut self) {
+        let Some(current_path) = self.get_current_path() else {
+            self.scroll_offset = 0;
+            return;
+        };
+
+        let column_index = self.windows.childrens[..current_path[0]]
+            .iter()
+            .filter(|children| children.value.mode == TilingMode::Managed)
+            .count() as i32;
+        let area = self.layout_options.get_layout_area(&self.monitor.work_area);
+        let column_width = self.layout_options.get_column_width(&area);
+        let column_left = column_index * column_width;
+        let column_right = column_left + column_width;
+
+        if column_left < self.scroll_offset {
+            self.scroll_offset = column_left;
+        } else if column_right > self.scroll_offset + area.width() {
+            self.scroll_offset = column_right - area.width();
+        }
+    }
+
+    pub fn arrange_windows(&mut self) {
+        if !self.visible {
+            return;
+        }
+
+        self.scroll_to_current();
+        let plan = self.compute_layout();
+        self.layout_applier.apply(self.system.as_ref(), &plan);
+        self.raise_stacked_windows(&self.windows);
+        self.raise_windows(&self.windows, &TilingMode::Monocle);
+        self.raise_windows(&self.windows, &TilingMode::Floating);
+    }
+
+    /// Put the active childrens of the stacked containers above the other ones
+    fn raise_stacked_windows(&self, current_node: &Node<Window>) {
+        if current_node.container_mode == ContainerMode::Stacked {
+            if let Some(active_children) = current_node.get_active_children() {
+                if active_children.is_leaf() {
+                    self.system.put_on_top(active_children.value.hwnd);
+                }
+                for path in active_children.leaf_paths() {
+                    if let Some(node) = active_children.get(&path) {
+                        self.system.put_on_top(node.value.hwnd);
+                    }
+                }
+            }
+        }
+
+        for children in current_node.childrens.iter() {
+            self.raise_stacked_windows(children);
+        }
+    }
+
+    fn raise_windows(&self, current_node: &WindowType, mode: &TilingMode) {
+        for children in current_node.childrens.iter() {
+            if children.is_leaf() {
+                if children.value.mode == *mode {
+                    self.system.put_on_top(children.value.hwnd);
+                }
+            } else {
+                self.raise_windows(children, mode);
+            }
+        }
+    }
+
     pub fn is_current_workspace(&self, x: i32, y: i32) -> bool {
-        self.monitor.is_point_in_monitor(x, y)
+        self.visible && self.monitor.is_point_in_monitor(x, y)
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Park every window outside of the monitor, the floating ones remembering where they were
+    pub fn hide(&mut self) {
+        if !self.visible {
+            return;
+        }
+
+        for path in self.windows.leaf_paths() {
+            let Some(node) = self.windows.get_mut(&path) else {
+                continue;
+            };
+            // The minimised windows are parked when they are restored
+            if node.value.minimized {
+                continue;
+            }
+            if node.value.mode == TilingMode::Floating {
+                node.value.floating_rect = self.system.get_window_rect(node.value.hwnd);
+            }
+
+            let hwnd = node.value.hwnd;
+            self.park_window(hwnd);
+        }
+
+        self.visible = false;
+        self.layout_applier.invalidate();
+    }
+
+    /// Bring back the floating windows, the next arrangement restores the other ones
+    ///
+    /// The floating windows added while the workspace was hidden are placed like new ones
+    pub fn show(&mut self) {
+        if self.visible {
+            return;
+        }
+
+        for path in self.windows.leaf_paths() {
+            let Some(node) = self.windows.get(&path) else {
+                continue;
+            };
+            if node.value.mode == TilingMode::Floating && !node.value.minimized {
+                let rect = self.get_floating_rect(&node.value);
+                self.system.set_window_pos(
+                    node.value.hwnd,
+                    rect.left,
+                    rect.top,
+                    rect.width(),
+                    rect.height(),
+                );
+            }
+        }
+
+        self.visible = true;
+        self.layout_applier.invalidate();
+    }
+
+    /// Show the workspace and put every window on the monitor, the parked tabs and columns included
+    pub fn restore_windows(&mut self) {
+        self.show();
+
+        for (hwnd, rect) in self.compute_layout() {
+            let rect = if is_parked_rect(&rect) {
+                self.monitor.work_area
+            } else {
+                rect
+            };
+            self.system
+                .set_window_pos(hwnd, rect.left, rect.top, rect.width(), rect.height());
+        }
+        self.layout_applier.invalidate();
+    }
+
+    fn park_window(&self, hwnd: isize) {
+        if let Some(rect) = self.system.get_window_rect(hwnd) {
+            let parked_rect = get_parked_rect(&rect);
+            self.system.set_window_pos(
+                hwnd,
+                parked_rect.left,
+                parked_rect.top,
+                parked_rect.width(),
+                parked_rect.height(),
+            );
+        }
     }
 
     pub fn set_current_next(&mut self) {
-        if self.windows.childrens.is_empty() {
+        let windows_count = self.windows.leaf_paths().len();
+        if windows_count == 0 {
             return;
         }
 
         // TODO: Allow to insert the next opened window after the one we are at (Do not need to advance if we are at the end of the tree)
-        self.current_window_index += 1;
+        // The minimised windows are skipped
+        for _ in 0..windows_count {
+            self.current_window_index += 1;
 
-        if self.current_window_index >= self.windows.childrens.len() {
-            self.current_window_index = 0;
+            if self.current_window_index >= windows_count {
+                self.current_window_index = 0;
+            }
+            if !self.is_current_minimized() {
+                break;
+            }
         }
+        self.activate_current_path();
+        self.scroll_to_current();
     }
 
     pub fn set_current_previous(&mut self) {
-        if self.windows.childrens.is_empty() {
+        let windows_count = self.windows.leaf_paths().len();
+        if windows_count == 0 {
             return;
         }
 
-        if self.current_window_index == 0 {
-            self.current_window_index = self.windows.childrens.len();
+        // The minimised windows are skipped
+        for _ in 0..windows_count {
+            if self.current_window_index == 0 || self.current_window_index > windows_count {
+                self.current_window_index = windows_count;
+            }
+
+            // TODO: Allow to insert the next opened window before the one we are at (Do not need to advance if we are at the end of the tree)
+            self.current_window_index -= 1;
+            if !self.is_current_minimized() {
+                break;
+            }
+        }
+        self.activate_current_path();
+        self.scroll_to_current();
+    }
+
+    /// Focus the nearest window in the direction, returning false when there is none
+    pub fn focus_direction(&mut self, direction: &Direction) -> bool {
+        let Some(hwnd) = self.get_neighbour(direction) else {
+            return false;
+        };
+
+        self.set_current_window(hwnd);
+        self.scroll_to_current();
+        true
+    }
+
+    /// Focus the visible window nearest to the rect in the direction, returning false when there is none
+    ///
+    /// The rect comes from another workspace, so the current window of this one is a candidate too
+    pub fn focus_nearest(&mut self, rect: &Rect, direction: &Direction) -> bool {
+        let Some(hwnd) = self.get_nearest_visible(rect, direction, None) else {
+            return false;
+        };
+
+        self.set_current_window(hwnd);
+        self.scroll_to_current();
+        true
+    }
+
+    /// Swap the current window with its neighbour in the direction, returning false when there is none
+    pub fn swap_current_direction(&mut self, direction: &Direction) -> bool {
+        let Some(neighbour_hwnd) = self.get_neighbour(direction) else {
+            return false;
+        };
+        let Some(current_path) = self.get_current_path() else {
+            return false;
+        };
+        let Some(neighbour_path) = self
+            .windows
+            .find_leaf_path(|window| window.hwnd == neighbour_hwnd)
+        else {
+            return false;
+        };
+
+        let current_window = self.windows.get(&current_path).unwrap().value.clone();
+        let neighbour_window = self.windows.get(&neighbour_path).unwrap().value.clone();
+        let hwnd = current_window.hwnd;
+        self.windows.get_mut(&current_path).unwrap().value = neighbour_window;
+        self.windows.get_mut(&neighbour_path).unwrap().value = current_window;
+
+        self.set_current_window(hwnd);
+        self.scroll_to_current();
+        true
+    }
+
+    /// Move the current window past its neighbour in the direction, returning false when there is none
+    pub fn move_current_direction(&mut self, direction: &Direction) -> bool {
+        let Some(neighbour_hwnd) = self.get_neighbour(direction) else {
+            return false;
+        };
+        let Some(current_window) = self.get_current_window() else {
+            return false;
+        };
+        let window = current_window.value.clone();
+        let hwnd = window.hwnd;
+
+        Self::remove_window(&mut self.windows, hwnd);
+        let Some(neighbour_path) = self
+            .windows
+            .find_leaf_path(|window| window.hwnd == neighbour_hwnd)
+        else {
+            return false;
+        };
+
+        let (axis, before) = match direction {
+            Direction::Left => (TilingDirection::Horizontal, true),
+            Direction::Right => (TilingDirection::Horizontal, false),
+            Direction::Up => (TilingDirection::Vertical, true),
+            Direction::Down => (TilingDirection::Vertical, false),
+        };
+        let (index, parent_path) = neighbour_path.split_last().unwrap();
+        let neighbour_direction = self.windows.get(&neighbour_path).unwrap().direction.clone();
+
+        // The layouts placing the root childrens in slots ignore their direction, only the order matters
+        let is_slot = parent_path.is_empty()
+            && !matches!(
+                self.layout_options.layout,
+                Layout::Tiling | Layout::Dwindle | Layout::Spiral
+            );
+
+        if is_slot || neighbour_direction == axis {
+            if let Some(parent) = self.windows.get_mut(parent_path) {
+                parent.insert(
+                    window,
+                    if before { *index } else { index + 1 },
+                    neighbour_direction,
+                );
+            }
+        } else if let Some(container) =
+            Self::wrap_in_container(&mut self.windows, &neighbour_path, axis.clone())
+        {
+            container.insert(window, if before { 0 } else { 1 }, axis);
+        }
+
+        self.set_current_window(hwnd);
+        self.scroll_to_current();
+        true
+    }
+
+    /// Get the window next to the current one in the direction
+    fn get_neighbour(&self, direction: &Direction) -> Option<isize> {
+        let current_rect = self.get_current_rect()?;
+        let current_hwnd = self.get_current_window().map(|node| node.value.hwnd);
+        if let Some(hwnd) = self.get_nearest_visible(&current_rect, direction, current_hwnd) {
+            return Some(hwnd);
+        }
+
+        // The scrolling layout parks the columns outside of the monitor, they are reached by order
+        if self.layout_options.layout == Layout::Scrolling {
+            let offset = match direction {
+                Direction::Left => -1,
+                Direction::Right => 1,
+                _ => return None,
+            };
+            return self.get_column_window(offset);
         }
 
-        // TODO: Allow to insert the next opened window before the one we are at (Do not need to advance if we are at the end of the tree)
-        self.current_window_index -= 1;
+        None
     }
 
-    fn arrange_recursive(
+    /// Get the visible window nearest to the rect in the direction, other than the excluded one
+    fn get_nearest_visible(
         &self,
-        current_node: &WindowType,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-    ) {
-        let borrowed_node = current_node;
-        if borrowed_node.is_leaf() {
+        rect: &Rect,
+        direction: &Direction,
+        exclude: Option<isize>,
+    ) -> Option<isize> {
+        let candidates: LayoutPlan = self
+            .get_visible_layout()
+            .into_iter()
+            .filter(|(hwnd, _)| Some(*hwnd) != exclude)
+            .collect();
+
+        get_nearest_in_direction(&candidates, rect, direction)
+    }
+
+    /// Get the first window of the column at the offset from the current one
+    fn get_column_window(&self, offset: isize) -> Option<isize> {
+        let current_path = self.get_current_path()?;
+        let column_index = current_path[0].checked_add_signed(offset)?;
+        let column = self.windows.childrens.get(column_index)?;
+
+        match column.leaf_paths().first() {
+            Some(path) => column.get(path).map(|node| node.value.hwnd),
+            None => Some(column.value.hwnd),
+        }
+    }
+
+    /// Get the rect of the current window in the computed layout, or on screen if it floats
+    pub fn get_current_rect(&self) -> Option<Rect> {
+        let current_window = &self.get_current_window()?.value;
+        if current_window.mode == TilingMode::Floating {
+            return self.system.get_window_rect(current_window.hwnd);
+        }
+
+        let current_hwnd = current_window.hwnd;
+        self.compute_layout()
+            .into_iter()
+            .find(|(hwnd, _)| *hwnd == current_hwnd)
+            .map(|(_, rect)| rect)
+    }
+
+    /// Get the computed layout without the windows parked outside of the monitor
+    fn get_visible_layout(&self) -> LayoutPlan {
+        self.compute_layout()
+            .into_iter()
+            .filter(|(_, rect)| {
+                let (center_x, center_y) = rect.center();
+                self.monitor.rect.contains(center_x, center_y)
+            })
+            .collect()
+    }
+
+    /// Display the workspace on another monitor, the layout following its size on the next arrangement
+    pub fn set_monitor(&mut self, monitor: Monitor) {
+        let offset_x = monitor.work_area.left - self.monitor.work_area.left;
+        let offset_y = monitor.work_area.top - self.monitor.work_area.top;
+
+        // The floating windows keep their position relative to the monitor
+        for path in self.windows.leaf_paths() {
+            let Some(node) = self.windows.get_mut(&path) else {
+                continue;
+            };
+            if node.value.mode != TilingMode::Floating {
+                continue;
+            }
+
+            let rect = if self.visible {
+                self.system.get_window_rect(node.value.hwnd)
+            } else {
+                node.value.floating_rect
+            };
+            let Some(rect) = rect else {
+                continue;
+            };
+
+            let moved_rect = Rect::new(
+                rect.left + offset_x,
+                rect.top + offset_y,
+                rect.right + offset_x,
+                rect.bottom + offset_y,
+            );
+            node.value.floating_rect = Some(moved_rect);
+            if self.visible {
+                self.system.set_window_pos(
+                    node.value.hwnd,
+                    moved_rect.left,
+                    moved_rect.top,
+                    moved_rect.width(),
+                    moved_rect.height(),
+                );
+            }
+        }
+
+        self.monitor = monitor;
+        self.scroll_offset = 0;
+        self.layout_applier.invalidate();
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_monitor(&self) -> &Monitor {
+        &self.monitor
+    }
+
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.layout_options.layout = layout;
+    }
+
+    pub fn change_master_ratio(&mut self, delta: f32) {
+        self.layout_options.master_ratio =
+            (self.layout_options.master_ratio + delta).clamp(MIN_MASTER_RATIO, MAX_MASTER_RATIO);
+    }
+
+    pub fn change_master_count(&mut self, delta: isize) {
+        self.layout_options.master_count = self
+            .layout_options
+            .master_count
+            .saturating_add_signed(delta);
+    }
+
+    /// Swap the current window with the first master, or with the next window if it is already the master
+    pub fn swap_current_with_master(&mut self) {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
+        let Some(current_hwnd) = self.windows.get(&current_path).map(|node| node.value.hwnd) else {
+            return;
+        };
+
+        // The master slots hold the root childrens, nested windows move with their container
+        let current_index = current_path[0];
+        let swapped_index = if current_index == 0 { 1 } else { 0 };
+        if swapped_index >= self.windows.childrens.len() {
             return;
         }
 
-        let mut child_x = x;
-        let mut child_y = y;
+        self.windows.childrens.swap(current_index, swapped_index);
+        self.set_current_window(current_hwnd);
+    }
 
-        let managed_childrens: Vec<&WindowType> = borrowed_node
-            .childrens
-            .iter()
-            .filter(|item| item.clone().value.mode == TilingMode::Managed)
-            .collect();
-        let width_ratio = width / managed_childrens.len() as i32;
-        let height_ratio = height / managed_childrens.len() as i32;
-
-        for children in borrowed_node.childrens.iter() {
-            let borrowed_children = children;
-            match borrowed_children.value.mode {
-                TilingMode::Managed => {
-                    let child_width = if borrowed_children.direction == TilingDirection::Horizontal
-                    {
-                        width_ratio
-                    } else {
-                        width
-                    };
-                    let child_height = if borrowed_children.direction == TilingDirection::Vertical {
-                        height_ratio
-                    } else {
-                        height
-                    };
-                    if borrowed_children.is_leaf() {
-                        let new_width = child_width;
-                        let new_height = child_height;
-                        let new_x = child_x;
-                        let new_y = child_y;
-
-                        borrowed_children
-                            .value
-                            .set_window_pos(new_x, new_y, new_width, new_height);
-                    } else {
-                        self.arrange_recursive(
-                            children,
-                            child_x,
-                            child_y,
-                            child_width,
-                            child_height,
-                        )
-                    }
+    /// Change the share of the nearest node around the current window splitting in the given direction
+    pub fn resize_current(&mut self, direction: &TilingDirection, delta: f32) {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
 
-                    match borrowed_children.direction {
-                        TilingDirection::Vertical => child_y += child_height,
-                        TilingDirection::Horizontal => child_x += child_width,
-                    }
-                }
-                TilingMode::Monocle => {
-                    if borrowed_children.is_leaf() {
-                        borrowed_children.value.set_window_pos(
-                            self.monitor.rect.left,
-                            self.monitor.rect.top,
-                            self.monitor.width,
-                            self.monitor.height,
-                        );
-                        borrowed_children.value.put_on_top();
-                    } else {
-                        let child_width =
-                            if borrowed_children.direction == TilingDirection::Horizontal {
-                                width_ratio
-                            } else {
-                                width
-                            };
-                        let child_height =
-                            if borrowed_children.direction == TilingDirection::Vertical {
-                                height_ratio
-                            } else {
-                                height
-                            };
-                        self.arrange_recursive(
-                            children,
-                            child_x,
-                            child_y,
-                            child_width,
-                            child_height,
-                        )
-                    }
+        for depth in (1..=current_path.len()).rev() {
+            let parent_path = &current_path[..depth - 1];
+            let has_siblings = self
+                .windows
+                .get(parent_path)
+                .map(|parent| {
+                    parent
+                        .childrens
+                        .iter()
+                        .filter(|children| children.value.mode == TilingMode::Managed)
+                        .count()
+                        > 1
+                })
+                .unwrap_or(false);
+
+            if let Some(node) = self.windows.get_mut(&current_path[..depth]) {
+                if has_siblings && node.direction == *direction {
+                    node.weight = (node.weight + delta).max(MIN_WEIGHT);
+                    return;
                 }
             }
         }
     }
 
+    pub fn balance_weights(&mut self) {
+        self.windows.reset_weights();
+    }
+
     pub fn set_current_tiling_mode(&mut self, mode: &TilingMode) {
-        if let Some(window) = self
-            .windows
-            .childrens
-            .get_mut(self.current_window_index)
-            .take()
-        {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
+
+        let Some(window) = self.windows.get(&current_path) else {
+            return;
+        };
+        let window = window.value.clone();
+
+        let mut floating_rect = window.floating_rect;
+        if window.mode == TilingMode::Floating && *mode != TilingMode::Floating {
+            // Remember where the user left the window to give it back the next time it floats
+            floating_rect = self.system.get_window_rect(window.hwnd).or(floating_rect);
+        } else if window.mode != TilingMode::Floating && *mode == TilingMode::Floating {
+            let rect = self.get_floating_rect(&window);
+            self.system.set_window_pos(
+                window.hwnd,
+                rect.left,
+                rect.top,
+                rect.width(),
+                rect.height(),
+            );
+            self.system.put_on_top(window.hwnd);
+        }
+
+        if let Some(window) = self.windows.get_mut(&current_path) {
             window.value.set_mode(mode.clone());
+            window.value.floating_rect = floating_rect;
+        }
+    }
+
+    /// Add the window floating over the center of the monitor
+    pub fn add_centered_window(&mut self, mut window: Window) {
+        let rect = self.get_placed_rect(window.hwnd, &FloatingPlacement::Center);
+        self.system.set_window_pos(
+            window.hwnd,
+            rect.left,
+            rect.top,
+            rect.width(),
+            rect.height(),
+        );
+        self.system.put_on_top(window.hwnd);
+
+        window.set_mode(TilingMode::Floating);
+        self.add_window(window);
+    }
+
+    /// Make the current window follow the monitor across workspaces, a tiled window starts floating
+    pub fn toggle_current_sticky(&mut self) {
+        let Some(current_path) = self.get_current_path() else {
+            return;
+        };
+        let Some(node) = self.windows.get_mut(&current_path) else {
+            return;
+        };
+
+        node.value.sticky = !node.value.sticky;
+        if node.value.sticky && node.value.mode == TilingMode::Managed {
+            self.set_current_tiling_mode(&TilingMode::Floating);
+        }
+    }
+
+    /// Remove the sticky windows from the workspace, to give them to the next one shown on the monitor
+    pub fn take_sticky_windows(&mut self) -> Vec<Window> {
+        let sticky_windows: Vec<Window> = self
+            .windows
+            .leaf_paths()
+            .iter()
+            .filter_map(|path| self.windows.get(path))
+            .filter(|node| node.value.sticky)
+            .map(|node| node.value.clone())
+            .collect();
+
+        for window in sticky_windows.iter() {
+            self.forget_window(window.hwnd);
+        }
+
+        sticky_windows
+    }
+
+    /// Move in the windows of the workspace this one replaces, keeping their tree if it has none
+    pub fn take_windows_from(&mut self, previous: &mut Workspace<S>) {
+        // The floating windows parked with a hidden workspace get their geometry back
+        previous.show();
+        let current_hwnd = previous.get_current_window().map(|node| node.value.hwnd);
+
+        if self.windows.childrens.is_empty() {
+            let empty_tree = Box::new(Node::new(Window::container(), TilingDirection::Vertical));
+            self.windows = std::mem::replace(&mut previous.windows, empty_tree);
+            if !self.visible {
+                // Park the windows the way hiding the workspace does
+                self.visible = true;
+                self.hide();
+            }
+        } else {
+            let windows: Vec<Window> = previous
+                .windows
+                .leaf_paths()
+                .iter()
+                .filter_map(|path| previous.windows.get(path))
+                .map(|node| node.value.clone())
+                .collect();
+            for window in windows {
+                self.add_window(window);
+            }
+        }
+
+        if let Some(current_hwnd) = current_hwnd {
+            self.set_current_window(current_hwnd);
+        }
+    }
+
+    /// Add the sticky windows left by another workspace without focusing them
+    pub fn add_sticky_windows(&mut self, windows: Vec<Window>) {
+        let current_hwnd = self.get_current_window().map(|node| node.value.hwnd);
+        for window in windows {
+            self.add_window(window);
+        }
+
+        if let Some(current_hwnd) = current_hwnd {
+            self.set_current_window(current_hwnd);
+        }
+    }
+
+    /// Float the current window, or give it back to the layout if it already floats
+    pub fn toggle_current_floating(&mut self) {
+        let Some(current_window) = self.get_current_window() else {
+            return;
+        };
+
+        let mode = match current_window.value.mode {
+            TilingMode::Floating => TilingMode::Managed,
+            _ => TilingMode::Floating,
+        };
+        self.set_current_tiling_mode(&mode);
+    }
+
+    /// Get the geometry of a window starting to float, the remembered one or one chosen by the placement policy
+    fn get_floating_rect(&self, window: &Window) -> Rect {
+        if let Some(floating_rect) = window.floating_rect {
+            return floating_rect;
         }
+
+        self.get_placed_rect(window.hwnd, &self.layout_options.floating_placement)
+    }
+
+    /// Get the geometry of the window placed on the monitor by the policy, keeping its size if it fits
+    fn get_placed_rect(&self, hwnd: isize, placement: &FloatingPlacement) -> Rect {
+        let area = self.monitor.work_area;
+        let (width, height) = match self.system.get_window_rect(hwnd) {
+            Some(rect) => (
+                rect.width().min(area.width()),
+                rect.height().min(area.height()),
+            ),
+            None => (area.width() / 2, area.height() / 2),
+        };
+
+        let (left, top) = match placement {
+            FloatingPlacement::Center => {
+                let (center_x, center_y) = area.center();
+                (center_x - width / 2, center_y - height / 2)
+            }
+            FloatingPlacement::UnderCursor => {
+                let (cursor_x, cursor_y) = self.system.get_cursor_position();
+                (cursor_x - width / 2, cursor_y - height / 2)
+            }
+            FloatingPlacement::Cascade => {
+                let floating_count = self
+                    .windows
+                    .leaf_paths()
+                    .iter()
+                    .filter_map(|path| self.windows.get(path))
+                    .filter(|node| node.value.mode == TilingMode::Floating)
+                    .count() as i32;
+                let offset = (floating_count + 1) * CASCADE_STEP;
+                (area.left + offset, area.top + offset)
+            }
+        };
+
+        // Keep the whole window inside the work area
+        let left = left.clamp(area.left, area.right - width);
+        let top = top.clamp(area.top, area.bottom - height);
+        Rect::new(left, top, left + width, top + height)
     }
 
     pub fn is_on_monitor(&self, monitor: isize) -> bool {
         self.monitor.monitor_handle == monitor
     }
+
+    pub fn contains_window(&self, hwnd: isize) -> bool {
+        self.windows
+            .find_leaf_path(|window| window.hwnd == hwnd)
+            .is_some()
+    }
+
+    /// Put a tiled window back in its place after it was moved by the user
+    pub fn handle_window_moved(&mut self, hwnd: isize) {
+        self.layout_applier
+            .forget_if_moved(self.system.as_ref(), hwnd);
+    }
 }